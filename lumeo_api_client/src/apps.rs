@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use uuid::Uuid;
 
@@ -16,6 +16,11 @@ pub struct Application {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ApplicationData {
+    pub name: String,
+}
+
 impl Client {
     pub async fn read_application(&self, application_id: Uuid) -> Result<Application> {
         let path = format!("/v1/apps/{application_id}");
@@ -26,4 +31,59 @@ impl Client {
         let path = format!("/v1/orgs/{organization_id}/apps");
         self.get(&path, None::<&()>).await
     }
+
+    pub async fn create_application(
+        &self,
+        organization_id: Uuid,
+        data: &ApplicationData,
+    ) -> Result<Application> {
+        let path = format!("/v1/orgs/{organization_id}/apps");
+        self.post(&path, data).await
+    }
+
+    pub async fn update_application(
+        &self,
+        application_id: Uuid,
+        data: &ApplicationData,
+    ) -> Result<Application> {
+        let path = format!("/v1/apps/{application_id}");
+        self.put(&path, data).await
+    }
+
+    /// Deletes the application along with all its gateways, cameras, streams and deployments.
+    pub async fn delete_application(&self, application_id: Uuid) -> Result<()> {
+        let path = format!("/v1/apps/{application_id}");
+        self.delete(&path, None::<&()>).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::orgs::OrganizationData;
+
+    #[tokio::test]
+    async fn application_round_trip() {
+        let server = crate::testing::MockServer::start().await.unwrap();
+        let client = server.client_builder().build().unwrap();
+
+        let org = client
+            .create_organization(&OrganizationData { name: "Acme".to_owned() })
+            .await
+            .unwrap();
+        let app = client
+            .create_application(org.id, &ApplicationData { name: "Warehouse".to_owned() })
+            .await
+            .unwrap();
+        assert_eq!(app.organization_id, org.id);
+
+        let app = client
+            .update_application(app.id, &ApplicationData { name: "Store".to_owned() })
+            .await
+            .unwrap();
+        assert_eq!(client.list_applications(org.id).await.unwrap()[0].name, "Store");
+
+        client.delete_application(app.id).await.unwrap();
+        assert!(client.read_application(app.id).await.unwrap_err().is_not_found());
+    }
 }
@@ -1,13 +1,17 @@
+use std::env;
+
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
 use url::Url;
 
 use super::Client;
 use crate::{
-    error::ErrorDetails, verify_response, Error::Reqwest, Method, Result, ResultExt,
+    error::ErrorDetails, verify_response, Error, Error::Reqwest, Method, Result, ResultExt,
     DEFAULT_LOGIN_TIMEOUT,
 };
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct LoginParams {
     pub email: String,
     pub password: String,
@@ -23,6 +27,7 @@ impl Client {
     // in a way that it needs to have an auth token to be created. We can't create a `Client`
     // without having done a login first. For that to work we need to call the associated function
     // `Client::login()` first in order to obtain a token and create `Client` from the returned token.
+    // `LoginCredentials` does exactly that and also logs in again once the token expires.
     pub async fn login(server_address: String, login_params: LoginParams) -> Result<LoginResponse> {
         let path = "/v1/internal/auth/login";
         let url =
@@ -42,3 +47,145 @@ impl Client {
             .http_context(Method::POST, path)
     }
 }
+
+/// Source of the bearer tokens used to authenticate API requests.
+///
+/// [`Client`] asks for a token before sending each request. When the server answers with
+/// [`ApiError::InvalidCredentials`](crate::error::ApiError::InvalidCredentials), the client calls
+/// [`refresh`](Self::refresh) and, if it returns `true`, replays the request once with a new token.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Returns the token to authenticate the next request with.
+    async fn token(&self) -> Result<String>;
+
+    /// Obtains a new token after `rejected_token` has been refused by the server.
+    ///
+    /// Returns whether the next call to [`token`](Self::token) will yield a different token.
+    async fn refresh(&self, rejected_token: &str) -> Result<bool> {
+        let _ = rejected_token;
+        Ok(false)
+    }
+}
+
+/// A fixed user or API token.
+#[derive(Clone)]
+pub struct StaticToken(pub String);
+
+#[async_trait]
+impl CredentialProvider for StaticToken {
+    async fn token(&self) -> Result<String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// The access token of a gateway, returned by [`Client::create_gateway`].
+///
+/// Gateway access tokens can't be refreshed: once rejected, the gateway has to be re-created.
+#[derive(Clone)]
+pub struct GatewayAccessToken(pub String);
+
+#[async_trait]
+impl CredentialProvider for GatewayAccessToken {
+    async fn token(&self) -> Result<String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A token read from an environment variable on every request, so that it can be rotated by
+/// updating the variable.
+#[derive(Clone)]
+pub struct EnvToken {
+    var: String,
+}
+
+impl EnvToken {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EnvToken {
+    async fn token(&self) -> Result<String> {
+        env::var(&self.var).map_err(|_| Error::TokenEnvVarMissing(self.var.clone()))
+    }
+
+    async fn refresh(&self, rejected_token: &str) -> Result<bool> {
+        Ok(self.token().await? != rejected_token)
+    }
+}
+
+/// A user token obtained by logging in with an email and password.
+///
+/// The login happens lazily before the first request and again whenever the server rejects the
+/// current token, e.g. because it has expired.
+pub struct LoginCredentials {
+    server_address: String,
+    login_params: LoginParams,
+    token: Mutex<Option<String>>,
+}
+
+impl LoginCredentials {
+    pub fn new(server_address: impl Into<String>, login_params: LoginParams) -> Self {
+        Self { server_address: server_address.into(), login_params, token: Mutex::new(None) }
+    }
+
+    async fn login(&self) -> Result<String> {
+        let LoginResponse { token } =
+            Client::login(self.server_address.clone(), self.login_params.clone()).await?;
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for LoginCredentials {
+    async fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        match &*token {
+            Some(token) => Ok(token.clone()),
+            None => Ok(token.insert(self.login().await?).clone()),
+        }
+    }
+
+    async fn refresh(&self, rejected_token: &str) -> Result<bool> {
+        let mut token = self.token.lock().await;
+
+        // Another request may have refreshed the token in the meantime.
+        if token.as_deref().map_or(false, |token| token != rejected_token) {
+            return Ok(true);
+        }
+
+        *token = Some(self.login().await?);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn static_token_is_never_refreshed() {
+        let credentials = StaticToken("token".to_owned());
+
+        assert_eq!(credentials.token().await.unwrap(), "token");
+        assert!(!credentials.refresh("token").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn env_token_follows_variable() {
+        const VAR: &str = "LUMEO_API_CLIENT_TEST_ENV_TOKEN";
+        let credentials = EnvToken::new(VAR);
+
+        env::remove_var(VAR);
+        assert!(matches!(credentials.token().await, Err(Error::TokenEnvVarMissing(_))));
+
+        env::set_var(VAR, "first");
+        assert_eq!(credentials.token().await.unwrap(), "first");
+        assert!(!credentials.refresh("first").await.unwrap());
+
+        env::set_var(VAR, "second");
+        assert!(credentials.refresh("first").await.unwrap());
+        assert_eq!(credentials.token().await.unwrap(), "second");
+    }
+}
@@ -0,0 +1,351 @@
+//! Synchronous facade over [`Client`](crate::Client), for code that doesn't run in an async
+//! runtime.
+//!
+//! ```no_run
+//! # use uuid::Uuid;
+//! let client = lumeo_api_client::Client::builder("https://api.lumeo.com", "token")
+//!     .application_id(Uuid::nil())
+//!     .build()?;
+//! let client = lumeo_api_client::blocking::Client::new(client)?;
+//!
+//! for camera in client.list_cameras(None)? {
+//!     println!("{}", camera.name);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{future::Future, io, net::IpAddr, path::Path, pin::Pin, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::runtime::{Builder, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    apps::{Application, ApplicationData},
+    cameras::{self, Camera, CameraData, CameraStatus, ReconcilePlan},
+    commands::camera::Camera as DiscoveredCamera,
+    deployments::{
+        self, BulkReport, Deployment, DeploymentData, DeploymentErrorEvent, DeploymentLogs,
+        NewDeployment, Selection, State, StateChange,
+    },
+    discovery_requests::DiscoveryRequestData,
+    error::Error,
+    events::{self, ErrorData, Event, EventData},
+    files::{
+        self, ChunkedUpload, DeleteParams, ExportFormat, File, FileCloudStatus, FileData,
+        LocalFileSync, SyncReport,
+    },
+    gateways::{Gateway, NewGateway},
+    metrics::VideoSourceMetric,
+    models::Model,
+    orgs::{Invitation, Member, NewInvitation, Organization, OrganizationData, Role},
+    pipeline::{Pipeline, UpdatePlan},
+    snapshots::SnapshotResponse,
+    streams::{Stream as VideoStream, StreamData},
+    Result,
+};
+
+/// Blocking client, running requests of the wrapped [`Client`](crate::Client) on an internal
+/// single-threaded runtime.
+///
+/// Methods block the calling thread until the request completes. They must not be called from
+/// within an async runtime, where they panic.
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+/// Blocking iterator over the records of a paginated list endpoint.
+pub struct Iter<'a, T> {
+    runtime: &'a Runtime,
+    stream: Pin<Box<dyn Stream<Item = Result<T>> + 'a>>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Blocking client scoped to a single gateway, see [`GatewayClient`](crate::GatewayClient).
+///
+/// Obtained with [`Client::gateway`].
+#[derive(Clone, Copy)]
+pub struct GatewayClient<'a> {
+    client: &'a Client,
+    inner: crate::GatewayClient<'a>,
+}
+
+macro_rules! blocking {
+    ($client:literal; $(fn $name:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!(
+                "Blocking version of [`", $client, "::", stringify!($name), "`]",
+                "(crate::", $client, "::", stringify!($name), ")."
+            )]
+            pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+                self.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+impl Client {
+    pub fn new(client: crate::Client) -> io::Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self { inner: client, runtime })
+    }
+
+    /// Scopes the client to a gateway of an application, for the endpoints that act on behalf of
+    /// a gateway.
+    pub fn gateway(&self, application_id: Uuid, gateway_id: Uuid) -> GatewayClient<'_> {
+        GatewayClient { client: self, inner: self.inner.app(application_id).gateway(gateway_id) }
+    }
+
+    /// The wrapped async client.
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> crate::Client {
+        self.inner
+    }
+
+    pub fn register_error_cb(&mut self, cb: impl Fn(&Error) + Send + Sync + 'static) {
+        self.inner.register_error_cb(cb);
+    }
+
+    pub fn get<T, Q>(&self, path: &str, query: Option<&Q>) -> Result<T>
+    where
+        T: DeserializeOwned,
+        Q: Serialize,
+    {
+        self.runtime.block_on(self.inner.get(path, query))
+    }
+
+    pub fn post<T, R>(&self, path: &str, body: &R) -> Result<T>
+    where
+        R: Serialize,
+        T: DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.post(path, body))
+    }
+
+    pub fn put<T, R>(&self, path: &str, body: &R) -> Result<T>
+    where
+        R: Serialize,
+        T: DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.put(path, body))
+    }
+
+    pub fn delete<Q>(&self, path: &str, query: Option<&Q>) -> Result<()>
+    where
+        Q: Serialize,
+    {
+        self.runtime.block_on(self.inner.delete(path, query))
+    }
+
+    /// Blocking version of [`Client::get_deployments_stream`](crate::Client::get_deployments_stream).
+    pub fn get_deployments_iter(&self, filter: deployments::ListParams) -> Iter<'_, Deployment> {
+        self.iter(self.inner.get_deployments_stream(filter))
+    }
+
+    /// Blocking version of
+    /// [`Client::get_deployment_updates_stream`](crate::Client::get_deployment_updates_stream).
+    pub fn get_deployment_updates_iter(
+        &self,
+        updated_ts_since: DateTime<Utc>,
+        filter: deployments::ListParams,
+    ) -> Iter<'_, Deployment> {
+        self.iter(self.inner.get_deployment_updates_stream(updated_ts_since, filter))
+    }
+
+    /// Blocking version of [`Client::list_files_stream`](crate::Client::list_files_stream).
+    pub fn list_files_iter(&self, params: files::ListParams) -> Iter<'_, File> {
+        self.iter(self.inner.list_files_stream(params))
+    }
+
+    /// Blocking version of [`Client::watch_deployment`](crate::Client::watch_deployment).
+    pub fn watch_deployment_iter(&self, deployment_id: Uuid) -> Iter<'_, StateChange> {
+        self.iter(self.inner.watch_deployment(deployment_id))
+    }
+
+    fn iter<'a, T>(&'a self, stream: impl Stream<Item = Result<T>> + 'a) -> Iter<'a, T> {
+        Iter { runtime: &self.runtime, stream: Box::pin(stream) }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    blocking! {
+        "Client";
+        fn get_orgs(&self) -> Result<Vec<Organization>>;
+        fn create_organization(&self, data: &OrganizationData) -> Result<Organization>;
+        fn read_organization(&self, organization_id: Uuid) -> Result<Organization>;
+        fn update_organization(&self, organization_id: Uuid, data: &OrganizationData) -> Result<Organization>;
+        fn delete_organization(&self, organization_id: Uuid) -> Result<()>;
+        fn list_members(&self, organization_id: Uuid) -> Result<Vec<Member>>;
+        fn set_member_role(&self, organization_id: Uuid, user_id: Uuid, role: Role) -> Result<()>;
+        fn remove_member(&self, organization_id: Uuid, user_id: Uuid) -> Result<()>;
+        fn invite_member(&self, organization_id: Uuid, invitation: &NewInvitation) -> Result<Invitation>;
+        fn list_invitations(&self, organization_id: Uuid) -> Result<Vec<Invitation>>;
+        fn revoke_invitation(&self, organization_id: Uuid, invitation_id: Uuid) -> Result<()>;
+
+        fn read_application(&self, application_id: Uuid) -> Result<Application>;
+        fn list_applications(&self, organization_id: Uuid) -> Result<Vec<Application>>;
+        fn create_application(&self, organization_id: Uuid, data: &ApplicationData) -> Result<Application>;
+        fn update_application(&self, application_id: Uuid, data: &ApplicationData) -> Result<Application>;
+        fn delete_application(&self, application_id: Uuid) -> Result<()>;
+
+        fn get_deployments(&self, filter: &deployments::ListParams) -> Result<Vec<Deployment>>;
+        fn create_deployment(&self, data: &NewDeployment) -> Result<Deployment>;
+        fn get_deployment(&self, deployment_id: Uuid) -> Result<Deployment>;
+        fn update_deployment(&self, deployment_id: Uuid, data: &DeploymentData) -> Result<Deployment>;
+        fn delete_deployment(&self, deployment_id: Uuid) -> Result<()>;
+        fn get_deployment_definition(&self, deployment_id: Uuid) -> Result<Pipeline>;
+        fn plan_deployment_update(&self, deployment_id: Uuid, pipeline: &Pipeline) -> Result<UpdatePlan>;
+        fn start_deployment(&self, deployment_id: Uuid) -> Result<()>;
+        fn stop_deployment(&self, deployment_id: Uuid) -> Result<()>;
+        fn checked_start_deployment(&self, deployment_id: Uuid) -> Result<()>;
+        fn checked_stop_deployment(&self, deployment_id: Uuid) -> Result<()>;
+        fn start_deployments(&self, selection: Selection, concurrency: usize) -> Result<BulkReport>;
+        fn stop_deployments(&self, selection: Selection, concurrency: usize) -> Result<BulkReport>;
+        fn delete_deployments(&self, selection: Selection, concurrency: usize) -> Result<BulkReport>;
+        fn get_deployment_logs(&self, deployment_id: Uuid) -> Result<DeploymentLogs>;
+        fn get_deployment_errors(&self, deployment_id: Uuid, limit: i16) -> Result<Vec<DeploymentErrorEvent>>;
+        fn wait_for_deployment_state(&self, deployment_id: Uuid, target: State, timeout: Duration) -> Result<Deployment>;
+
+        fn list_files(&self, params: Option<&files::ListParams>) -> Result<Vec<File>>;
+        fn create_file(&self, file_data: &FileData) -> Result<File>;
+        fn read_file(&self, file_id: Uuid) -> Result<File>;
+        fn update_file(&self, file_id: Uuid, file_data: &FileData) -> Result<File>;
+        fn update_cloud_status(&self, file_id: Uuid, cloud_status: &FileCloudStatus) -> Result<()>;
+        fn delete_file(&self, file_id: Uuid) -> Result<()>;
+        fn delete_files(&self, params: &DeleteParams) -> Result<()>;
+        fn upload_file_content_from(&self, file_id: Uuid, path: &Path) -> Result<()>;
+        fn upload_file_chunked(&self, file_id: Uuid, path: &Path, upload: &ChunkedUpload) -> Result<()>;
+        fn download_file_data_to(&self, file_id: Uuid, path: &Path) -> Result<u64>;
+        fn download_file_metadata_to(&self, file_id: Uuid, path: &Path) -> Result<u64>;
+        fn export_files_to(&self, params: files::ListParams, format: ExportFormat, path: &Path) -> Result<u64>;
+
+        fn read_camera(&self, camera_id: Uuid) -> Result<Camera>;
+        fn list_cameras(&self, filter: Option<&cameras::ListParams>) -> Result<Vec<Camera>>;
+        fn create_camera(&self, data: &CameraData) -> Result<Camera>;
+        fn delete_camera(&self, camera_id: Uuid) -> Result<()>;
+        fn list_camera_streams(&self, camera_id: Uuid) -> Result<Vec<VideoStream>>;
+        fn update_camera(&self, camera_id: Uuid, data: &CameraData) -> Result<Camera>;
+        fn set_camera_status(&self, camera_id: Uuid, status: &CameraStatus) -> Result<()>;
+
+        fn create_stream(&self, stream: &StreamData) -> Result<VideoStream>;
+        fn read_stream(&self, stream_id: Uuid) -> Result<VideoStream>;
+        fn update_stream(&self, stream_id: Uuid, stream: &StreamData) -> Result<VideoStream>;
+        fn delete_stream(&self, stream_id: Uuid) -> Result<()>;
+
+        fn create_gateway(&self, application_id: Uuid, gateway: &NewGateway) -> Result<Gateway>;
+
+        fn list_events(&self, params: &events::ListParams) -> Result<Vec<Event>>;
+        fn create_event(&self, event: &EventData) -> Result<Event>;
+        fn create_error_event(&self, error_data: &ErrorData) -> Result<Event>;
+        fn push_video_source_metric(&self, gateway_id: Uuid, metric: &VideoSourceMetric) -> Result<()>;
+
+        fn read_model(&self, model_id: Uuid) -> Result<Model>;
+        fn read_marketplace_model(&self, model_id: Uuid) -> Result<Model>;
+
+        fn take_camera_snapshot(&self, camera_id: Uuid) -> Result<SnapshotResponse>;
+        fn take_stream_snapshot(&self, stream_id: Uuid) -> Result<SnapshotResponse>;
+        fn set_camera_snapshot_file_id(&self, camera_id: Uuid, snapshot_file_id: Uuid) -> Result<()>;
+        fn set_stream_snapshot_file_id(&self, stream_id: Uuid, snapshot_file_id: Uuid) -> Result<()>;
+    }
+}
+
+impl<'a> GatewayClient<'a> {
+    pub fn client(self) -> &'a Client {
+        self.client
+    }
+
+    /// The wrapped async client.
+    pub fn inner(self) -> crate::GatewayClient<'a> {
+        self.inner
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.client.block_on(future)
+    }
+
+    blocking! {
+        "GatewayClient";
+        fn read_gateway(&self) -> Result<Gateway>;
+        fn list_linked_cameras(&self) -> Result<Vec<Camera>>;
+        fn update_gateway_ip_local(&self, ip: &IpAddr) -> Result<()>;
+        fn update_gateway_ip_ext(&self, ip: &IpAddr) -> Result<()>;
+        fn put_discovery_response(&self, request_id: Uuid, data: &DiscoveryRequestData) -> Result<()>;
+        fn set_cameras_statuses(&self, cameras: &[CameraData]) -> Result<()>;
+        fn link_camera(&self, camera_id: Uuid) -> Result<()>;
+        fn unlink_camera(&self, camera_id: Uuid) -> Result<()>;
+        fn reconcile_cameras(&self, discovered: &[DiscoveredCamera]) -> Result<ReconcilePlan>;
+        fn sync_local_files(&self, sync: &LocalFileSync) -> Result<SyncReport>;
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::thread;
+
+    use futures::future;
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{MockServer, Resource};
+
+    /// Runs a mock server on its own runtime, as a blocking client can't be used within one.
+    fn start_server() -> MockServer {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let server = runtime.block_on(MockServer::start()).unwrap();
+        thread::spawn(move || runtime.block_on(future::pending::<()>()));
+        server
+    }
+
+    #[test]
+    fn performs_requests() {
+        let server = start_server();
+        let application_id = Uuid::new_v4();
+        let camera_id = server.insert(
+            Resource::Cameras,
+            json!({ "application_id": application_id, "name": "Lobby", "status": "online" }),
+        );
+        let client =
+            Client::new(server.client_builder().application_id(application_id).build().unwrap())
+                .unwrap();
+
+        let cameras = client.list_cameras(None).unwrap();
+        assert_eq!(cameras.len(), 1);
+        assert_eq!(cameras[0].id, camera_id);
+
+        client.set_camera_status(camera_id, &CameraStatus::Offline).unwrap();
+        assert_eq!(client.read_camera(camera_id).unwrap().status, CameraStatus::Offline);
+
+        let gateway_id = server.insert(
+            Resource::Gateways,
+            json!({ "application_id": application_id, "name": "Edge", "status": "online" }),
+        );
+        let gateway = client.gateway(application_id, gateway_id);
+        assert_eq!(gateway.read_gateway().unwrap().name, "Edge");
+        assert!(gateway.list_linked_cameras().unwrap().is_empty());
+    }
+
+    #[test]
+    fn returns_errors() {
+        let server = start_server();
+        let client =
+            Client::new(server.client_builder().application_id(Uuid::new_v4()).build().unwrap())
+                .unwrap();
+
+        assert!(client.read_stream(Uuid::new_v4()).is_err());
+    }
+}
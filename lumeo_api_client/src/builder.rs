@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{
+    auth::{CredentialProvider, StaticToken},
+    middleware::Middleware,
+    retry::RetryPolicy,
+    Client, DEFAULT_TIMEOUT, DEFAULT_TRANSFER_TIMEOUT,
+};
+
+const DEFAULT_USER_AGENT: &str = "Lumeo api-client";
+
+/// Configures and creates a [`Client`].
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use lumeo_api_client::{ClientBuilder, RetryPolicy};
+/// let client = ClientBuilder::new("https://api.lumeo.com", "token")
+///     .connect_timeout(Duration::from_secs(5))
+///     .retry_policy(RetryPolicy { max_retries: 5, ..Default::default() })
+///     .build()?;
+/// # Ok::<(), reqwest::Error>(())
+/// ```
+#[must_use]
+pub struct ClientBuilder {
+    pub(crate) base_url: String,
+    pub(crate) credentials: Box<dyn CredentialProvider>,
+    pub(crate) application_id: Option<Uuid>,
+    pub(crate) gateway_id: Option<Uuid>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) proxy: Option<reqwest::Proxy>,
+    pub(crate) timeout: Duration,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) transfer_timeout: Duration,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) http_client: Option<reqwest::Client>,
+    pub(crate) middlewares: Vec<Box<dyn Middleware>>,
+}
+
+impl ClientBuilder {
+    pub fn new(base_url: impl Into<String>, auth_token: impl Into<String>) -> Self {
+        Self::with_credentials(base_url, StaticToken(auth_token.into()))
+    }
+
+    /// Creates a builder for a client authenticating with tokens from `credentials`.
+    pub fn with_credentials(
+        base_url: impl Into<String>,
+        credentials: impl CredentialProvider + 'static,
+    ) -> Self {
+        Self {
+            base_url: base_url.into(),
+            credentials: Box::new(credentials),
+            application_id: None,
+            gateway_id: None,
+            user_agent: None,
+            proxy: None,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: None,
+            transfer_timeout: DEFAULT_TRANSFER_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            http_client: None,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Scopes the client to an application.
+    pub fn application_id(mut self, application_id: Uuid) -> Self {
+        self.application_id = Some(application_id);
+        self
+    }
+
+    /// Scopes the client to a gateway.
+    pub fn gateway_id(mut self, gateway_id: Uuid) -> Self {
+        self.gateway_id = Some(gateway_id);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Timeout of a single call, from sending the request until the response body has been read.
+    ///
+    /// Each retry gets its own timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout for establishing a connection to the server.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Timeout of uploads and downloads of file content, which replaces the timeout of single
+    /// calls for them. One hour by default.
+    pub fn transfer_timeout(mut self, transfer_timeout: Duration) -> Self {
+        self.transfer_timeout = transfer_timeout;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Appends a middleware to the chain run around every request.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Uses a preconfigured HTTP client.
+    ///
+    /// User agent, proxy and timeouts set on this builder are ignored in that case, except for
+    /// the transfer timeout.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> reqwest::Result<Client> {
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => {
+                let mut builder = reqwest::Client::builder()
+                    .timeout(self.timeout)
+                    .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+
+                builder.build()?
+            }
+        };
+
+        Ok(Client {
+            http_client,
+            base_url: self.base_url,
+            credentials: self.credentials,
+            application_id: self.application_id,
+            gateway_id: self.gateway_id,
+            retry_policy: self.retry_policy,
+            transfer_timeout: self.transfer_timeout,
+            middlewares: self.middlewares,
+            error_cb: None,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use std::cmp::Ordering;
+
+use num_rational::Rational32;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use super::{Camera, CameraData};
+use crate::{commands::camera::Capability, pipeline::Resolution};
+
+impl Capability {
+    pub fn resolution(&self) -> Resolution {
+        Resolution { width: self.width.max(0) as u32, height: self.height.max(0) as u32 }
+    }
+
+    /// The highest supported framerate, `None` if the framerates aren't known.
+    pub fn max_framerate(&self) -> Option<Rational32> {
+        self.framerates.iter().max().copied()
+    }
+
+    /// Whether the capability provides at least `resolution` at `framerate`. Capabilities with
+    /// unknown framerates are assumed to support any framerate.
+    pub fn satisfies(&self, resolution: &Resolution, framerate: Rational32) -> bool {
+        let own = self.resolution();
+        own.width >= resolution.width
+            && own.height >= resolution.height
+            && self.max_framerate().map_or(true, |max| max >= framerate)
+    }
+}
+
+/// Picks the capability best suited to capture at `resolution` and `framerate`.
+///
+/// This is the smallest capability satisfying the request (see [`Capability::satisfies`]). If
+/// none does, it's the one coming closest, preferring resolution over framerate.
+pub fn best_capability<'a>(
+    capabilities: &'a [Capability],
+    resolution: &Resolution,
+    framerate: Rational32,
+) -> Option<&'a Capability> {
+    let (satisfying, insufficient): (Vec<_>, Vec<_>) =
+        capabilities.iter().partition(|capability| capability.satisfies(resolution, framerate));
+
+    let area = |capability: &Capability| {
+        let resolution = capability.resolution();
+        u64::from(resolution.width) * u64::from(resolution.height)
+    };
+    if let Some(best) = satisfying
+        .into_iter()
+        .min_by(|a, b| area(a).cmp(&area(b)).then_with(|| compare_framerates(a, b)))
+    {
+        return Some(best);
+    }
+
+    // How much of the requested resolution and framerate is provided.
+    let provided = |capability: &Capability| {
+        let own = capability.resolution();
+        let width = u64::from(own.width.min(resolution.width));
+        let height = u64::from(own.height.min(resolution.height));
+        let framerate = capability.max_framerate().map_or(framerate, |max| max.min(framerate));
+        (width * height, framerate)
+    };
+    insufficient
+        .into_iter()
+        .max_by(|a, b| provided(a).cmp(&provided(b)).then(area(b).cmp(&area(a))))
+}
+
+fn compare_framerates(a: &Capability, b: &Capability) -> Ordering {
+    // Unknown framerates sort last, a known sufficient one is preferred.
+    match (a.max_framerate(), b.max_framerate()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }
+}
+
+/// Deserializes the capabilities of a camera record, which are an opaque JSON blob for the API.
+/// Blobs that aren't a list of capabilities are dropped instead of failing the whole record.
+pub(super) fn deserialize_lenient<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<Capability>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| serde_json::from_value(value).ok()))
+}
+
+impl Camera {
+    /// See [`best_capability`].
+    pub fn best_capability(
+        &self,
+        resolution: &Resolution,
+        framerate: Rational32,
+    ) -> Option<&Capability> {
+        best_capability(self.capabilities.as_deref()?, resolution, framerate)
+    }
+}
+
+impl CameraData {
+    /// See [`best_capability`].
+    pub fn best_capability(
+        &self,
+        resolution: &Resolution,
+        framerate: Rational32,
+    ) -> Option<&Capability> {
+        best_capability(self.capabilities.as_deref()?, resolution, framerate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capability(name: &str, width: i32, height: i32, framerates: &[i32]) -> Capability {
+        Capability {
+            name: name.into(),
+            width,
+            height,
+            framerates: framerates.iter().map(|&fps| Rational32::from_integer(fps)).collect(),
+            format: None,
+        }
+    }
+
+    fn best<'a>(capabilities: &'a [Capability], resolution: &str, fps: i32) -> &'a str {
+        let resolution = resolution.parse().unwrap();
+        let best = best_capability(capabilities, &resolution, Rational32::from_integer(fps));
+        &best.unwrap().name
+    }
+
+    #[test]
+    fn picks_smallest_satisfying_capability() {
+        let capabilities = [
+            capability("1080p", 1920, 1080, &[15, 30]),
+            capability("720p60", 1280, 720, &[30, 60]),
+            capability("720p", 1280, 720, &[15, 30]),
+            capability("480p", 640, 480, &[30]),
+        ];
+
+        assert_eq!(best(&capabilities, "1280x720", 30), "720p");
+        assert_eq!(best(&capabilities, "1280x720", 50), "720p60");
+        assert_eq!(best(&capabilities, "800x600", 25), "720p");
+        assert_eq!(best(&capabilities, "1920x1080", 30), "1080p");
+    }
+
+    #[test]
+    fn falls_back_to_closest_capability() {
+        let capabilities = [
+            capability("1080p", 1920, 1080, &[15]),
+            capability("720p", 1280, 720, &[30]),
+            capability("480p", 640, 480, &[60]),
+        ];
+
+        assert_eq!(best(&capabilities, "3840x2160", 30), "1080p");
+        assert_eq!(best(&capabilities, "1920x1080", 30), "1080p");
+        assert_eq!(best(&capabilities, "640x480", 120), "480p");
+        assert!(best_capability(&[], &"640x480".parse().unwrap(), 30.into()).is_none());
+    }
+
+    #[test]
+    fn deserializes_camera_capabilities() {
+        let data: CameraData = serde_json::from_value(serde_json::json!({
+            "status": "online",
+            "capabilities": [
+                { "name": "720p", "width": 1280, "height": 720, "framerates": [[30, 1]] }
+            ]
+        }))
+        .unwrap();
+
+        let capability =
+            data.best_capability(&"640x480".parse().unwrap(), Rational32::new(25, 1)).unwrap();
+        assert_eq!(capability.resolution(), "1280x720".parse().unwrap());
+        assert_eq!(capability.max_framerate(), Some(Rational32::new(30, 1)));
+    }
+
+    #[test]
+    fn ignores_malformed_capabilities() {
+        let parse = |capabilities| {
+            let data = serde_json::json!({ "status": "online", "capabilities": capabilities });
+            serde_json::from_value::<CameraData>(data).unwrap().capabilities
+        };
+
+        assert_eq!(parse(serde_json::json!({})), None);
+        assert_eq!(
+            parse(serde_json::json!([{ "width": 1280, "height": 720, "framerates": [] }])),
+            None
+        );
+        assert_eq!(parse(Value::Null), None);
+        assert_eq!(parse(serde_json::json!([])), Some(vec![]));
+        let data: CameraData =
+            serde_json::from_value(serde_json::json!({ "name": "Lobby" })).unwrap();
+        assert_eq!(data.capabilities, None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{convert::Infallible, fmt, str::FromStr};
+
+use serde::{
+    de::{Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+
+/// Connection type of a camera, the `conn_type` of camera records.
+///
+/// Connection types introduced by newer API versions are kept as `Unknown`, so that they survive
+/// a round trip through the client.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ConnType {
+    /// Connected to the gateway, e.g. over USB or CSI
+    Local,
+    /// Reachable over the network, e.g. an IP camera
+    Remote,
+    Unknown(String),
+}
+
+impl ConnType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ConnType::Local => "local",
+            ConnType::Remote => "remote",
+            ConnType::Unknown(conn_type) => conn_type,
+        }
+    }
+}
+
+impl FromStr for ConnType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "local" => ConnType::Local,
+            "remote" => ConnType::Remote,
+            conn_type => ConnType::Unknown(conn_type.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for ConnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ConnType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConnType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keeps_unknown_conn_types() {
+        let conn_type: ConnType = serde_json::from_value(json!("remote")).unwrap();
+        assert_eq!(conn_type, ConnType::Remote);
+
+        let conn_type: ConnType = serde_json::from_value(json!("virtual")).unwrap();
+        assert_eq!(conn_type, ConnType::Unknown("virtual".into()));
+        assert_eq!(serde_json::to_value(&conn_type).unwrap(), json!("virtual"));
+    }
+}
@@ -0,0 +1,275 @@
+//! Conversions between cameras discovered by gateways ([`commands::camera::Camera`]) and API
+//! camera records ([`CameraData`]).
+//!
+//! Camera records don't store the interface of local cameras. Converting a record back infers it
+//! from the URI: `file:///dev/video*` devices are USB cameras, other local cameras fail with
+//! [`CameraConversionError::UnknownInterface`].
+
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+use super::{CameraData, ConnType};
+use crate::{
+    commands::camera::{
+        Camera as DiscoveredCamera, LocalCamera, LocalCameraInterface, RemoteCamera, Status,
+        Stream as DiscoveredStream,
+    },
+    discovery_requests::DiscoveryResult,
+    streams::{StreamData, StreamSource, StreamType},
+};
+
+#[derive(Debug, Error)]
+pub enum CameraConversionError {
+    #[error("Camera has no `{0}`")]
+    MissingField(&'static str),
+    #[error("Unknown camera connection type '{0}'")]
+    UnknownConnType(String),
+    #[error("Unknown camera status '{0}'")]
+    UnknownStatus(String),
+    #[error("Unknown interface of local camera '{0}'")]
+    UnknownInterface(Url),
+}
+
+impl From<&DiscoveredCamera> for CameraData {
+    /// The manufacturer and the interface of local cameras aren't stored by the API, and the
+    /// streams of remote cameras are separate records, see [`RemoteCamera::stream_data`].
+    fn from(camera: &DiscoveredCamera) -> Self {
+        let mut data = CameraData {
+            status: Some(camera.status().clone().into()),
+            name: camera.name().map(Into::into),
+            model: camera.model().map(Into::into),
+            uri: Some(camera.uri().clone()),
+            ..Default::default()
+        };
+        match camera {
+            DiscoveredCamera::Local(camera) => {
+                data.conn_type = Some(ConnType::Local);
+                data.capabilities = Some(camera.capabilities.clone());
+            }
+            DiscoveredCamera::Remote(camera) => {
+                data.conn_type = Some(ConnType::Remote);
+                data.mac_address = Some(camera.mac_address.clone());
+                data.ip_local = camera.ip_local.clone();
+            }
+        }
+        data
+    }
+}
+
+impl From<DiscoveredCamera> for CameraData {
+    fn from(camera: DiscoveredCamera) -> Self {
+        Self::from(&camera)
+    }
+}
+
+impl TryFrom<&CameraData> for DiscoveredCamera {
+    type Error = CameraConversionError;
+
+    /// The interface of local cameras is inferred from their URI, see the [module
+    /// docs](self). Remote cameras have no streams.
+    fn try_from(data: &CameraData) -> Result<Self, Self::Error> {
+        use CameraConversionError::*;
+
+        let status = data.status.clone().ok_or(MissingField("status"))?;
+        let status = Status::try_from(status).map_err(UnknownStatus)?;
+        let uri = data.uri.clone().ok_or(MissingField("uri"))?;
+
+        match data.conn_type.as_ref().ok_or(MissingField("conn_type"))? {
+            ConnType::Local => Ok(DiscoveredCamera::Local(LocalCamera {
+                interface: local_interface(&uri).ok_or_else(|| UnknownInterface(uri.clone()))?,
+                uri,
+                status,
+                name: data.name.clone(),
+                manufacturer: None,
+                model: data.model.clone(),
+                capabilities: data.capabilities.clone().unwrap_or_default(),
+            })),
+            ConnType::Remote => Ok(DiscoveredCamera::Remote(RemoteCamera {
+                uri,
+                mac_address: data.mac_address.clone().ok_or(MissingField("mac_address"))?,
+                status,
+                name: data.name.clone(),
+                manufacturer: None,
+                model: data.model.clone(),
+                ip_local: data.ip_local.clone(),
+                streams: Vec::new(),
+            })),
+            ConnType::Unknown(conn_type) => Err(UnknownConnType(conn_type.clone())),
+        }
+    }
+}
+
+/// Returns the interface of the local camera at `uri`, if it can be told from the URI.
+fn local_interface(uri: &Url) -> Option<LocalCameraInterface> {
+    let is_video_device = uri.scheme() == "file" && uri.path().starts_with("/dev/video");
+    is_video_device.then(|| LocalCameraInterface::Usb)
+}
+
+impl TryFrom<CameraData> for DiscoveredCamera {
+    type Error = CameraConversionError;
+
+    fn try_from(data: CameraData) -> Result<Self, Self::Error> {
+        Self::try_from(&data)
+    }
+}
+
+impl From<&DiscoveredStream> for StreamData {
+    /// The stream isn't associated with a camera yet, see [`RemoteCamera::stream_data`].
+    fn from(stream: &DiscoveredStream) -> Self {
+        StreamData {
+            name: Some(stream.name.clone()),
+            source: StreamSource::CameraStream,
+            stream_type: StreamType::Rtsp,
+            gateway_id: None,
+            uri: stream.rtsp_uri.clone(),
+            status: None,
+            camera_id: None,
+            deployment_id: None,
+            node: None,
+            configuration: None,
+            snapshot_file_id: None,
+        }
+    }
+}
+
+impl RemoteCamera {
+    /// Returns the streams of the camera, to be created once the camera record exists.
+    pub fn stream_data(&self, camera_id: Uuid, gateway_id: Option<Uuid>) -> Vec<StreamData> {
+        self.streams
+            .iter()
+            .map(|stream| StreamData {
+                camera_id: Some(camera_id),
+                gateway_id,
+                ..StreamData::from(stream)
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<DiscoveredCamera>> for DiscoveryResult {
+    fn from(cameras: Vec<DiscoveredCamera>) -> Self {
+        DiscoveryResult::Success(cameras.iter().map(CameraData::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational32;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{cameras::CameraStatus, commands::camera::Capability};
+
+    fn local_camera() -> DiscoveredCamera {
+        DiscoveredCamera::Local(LocalCamera {
+            uri: "file:///dev/video0".parse().unwrap(),
+            status: Status::Online,
+            name: Some("Entrance".into()),
+            manufacturer: None,
+            model: Some("C920".into()),
+            interface: LocalCameraInterface::Usb,
+            capabilities: vec![Capability {
+                name: "720p".into(),
+                width: 1280,
+                height: 720,
+                framerates: vec![Rational32::new(30, 1)],
+                format: Some("MJPG".into()),
+            }],
+        })
+    }
+
+    fn remote_camera() -> RemoteCamera {
+        RemoteCamera {
+            uri: "http://192.168.0.42/device".parse().unwrap(),
+            mac_address: "00:11:22:33:44:55".into(),
+            status: Status::Unauthorized,
+            name: Some("Parking".into()),
+            manufacturer: Some("Acme".into()),
+            model: None,
+            ip_local: Some("192.168.0.42".into()),
+            streams: vec![DiscoveredStream {
+                rtsp_uri: "rtsp://192.168.0.42:554/hd_stream".parse().unwrap(),
+                name: "HD".into(),
+                capability: Capability {
+                    name: "1080p".into(),
+                    width: 1920,
+                    height: 1080,
+                    framerates: vec![],
+                    format: None,
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn converts_local_camera() {
+        let camera = local_camera();
+
+        let data = CameraData::from(&camera);
+        assert_eq!(data.conn_type, Some(ConnType::Local));
+        assert_eq!(data.status, Some(CameraStatus::Online));
+        assert_eq!(data.capabilities.as_ref().unwrap()[0].width, 1280);
+
+        assert_eq!(DiscoveredCamera::try_from(&data).unwrap(), camera);
+
+        let data = CameraData { uri: Some("rtsp://127.0.0.1/csi0".parse().unwrap()), ..data };
+        let err = DiscoveredCamera::try_from(data).unwrap_err();
+        assert!(
+            matches!(err, CameraConversionError::UnknownInterface(uri) if uri.scheme() == "rtsp")
+        );
+    }
+
+    #[test]
+    fn converts_remote_camera() {
+        let camera = remote_camera();
+
+        let data = CameraData::from(DiscoveredCamera::Remote(camera.clone()));
+        assert_eq!(data.conn_type, Some(ConnType::Remote));
+        assert_eq!(data.status, Some(CameraStatus::Unauthorized));
+        assert_eq!(data.mac_address.as_deref(), Some("00:11:22:33:44:55"));
+
+        let converted = match DiscoveredCamera::try_from(&data).unwrap() {
+            DiscoveredCamera::Remote(converted) => converted,
+            converted => panic!("unexpected camera {converted:?}"),
+        };
+        assert_eq!(
+            converted,
+            RemoteCamera { manufacturer: None, streams: vec![], ..camera.clone() }
+        );
+
+        let camera_id = Uuid::from_u128(1);
+        let streams = camera.stream_data(camera_id, None);
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].source, StreamSource::CameraStream);
+        assert_eq!(streams[0].camera_id, Some(camera_id));
+        assert_eq!(streams[0].uri.as_str(), "rtsp://192.168.0.42:554/hd_stream");
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let data = CameraData::from(local_camera());
+        let convert = |data: CameraData| DiscoveredCamera::try_from(data).unwrap_err();
+
+        let err =
+            convert(CameraData { conn_type: Some("virtual".parse().unwrap()), ..data.clone() });
+        assert!(
+            matches!(err, CameraConversionError::UnknownConnType(conn_type) if conn_type == "virtual")
+        );
+        let status = CameraStatus::Unknown("lost".into());
+        let err = convert(CameraData { status: Some(status), ..data.clone() });
+        assert!(matches!(err, CameraConversionError::UnknownStatus(_)));
+        let err = convert(CameraData { uri: None, ..data });
+        assert!(matches!(err, CameraConversionError::MissingField("uri")));
+    }
+
+    #[test]
+    fn builds_discovery_result() {
+        let result = DiscoveryResult::from(vec![local_camera()]);
+
+        assert_eq!(
+            serde_json::to_value(&result).unwrap()["result"][0]["uri"],
+            json!("file:///dev/video0")
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use uuid::Uuid;
+
+use super::{Camera, CameraData, CameraStatus};
+use crate::{
+    commands::camera::Camera as DiscoveredCamera,
+    streams::{Stream, StreamData},
+    GatewayClient, Result,
+};
+
+/// Changes bringing the cameras linked to a gateway in line with the cameras it discovered, see
+/// [`plan_reconciliation`].
+#[derive(Debug, Default)]
+pub struct ReconcilePlan {
+    /// Statuses for [`GatewayClient::set_cameras_statuses`], identifying cameras by MAC address
+    /// or URI.
+    ///
+    /// Only changes are listed: linked cameras whose status changed, linked cameras that weren't
+    /// discovered and aren't offline yet, and newly discovered cameras. The server leaves cameras
+    /// missing from the list unchanged.
+    pub statuses: Vec<CameraData>,
+    /// Updates of cameras whose discovered details changed, for
+    /// [`AppClient::update_camera`](crate::AppClient::update_camera). They only contain the
+    /// changed fields.
+    pub updates: Vec<(Uuid, CameraData)>,
+    /// Streams of remote cameras that aren't registered yet
+    pub streams: Vec<StreamData>,
+    /// Discovered cameras that aren't linked to the gateway
+    pub unmatched: Vec<DiscoveredCamera>,
+}
+
+impl ReconcilePlan {
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty() && self.updates.is_empty() && self.streams.is_empty()
+    }
+}
+
+/// Plans the reconciliation of the cameras `discovered` by a gateway with the cameras `linked` to
+/// it and their registered `streams`.
+///
+/// Cameras are matched by MAC address, or by URI if either has no MAC address. A linked camera
+/// is matched at most once, further discovered cameras matching it are unmatched but get no
+/// status, as it would overwrite the matched one. Names are managed in the API and never updated.
+pub fn plan_reconciliation(
+    gateway_id: Uuid,
+    discovered: &[DiscoveredCamera],
+    linked: &[Camera],
+    streams: &[Stream],
+) -> ReconcilePlan {
+    let mut plan = ReconcilePlan::default();
+    let mut matched = Vec::new();
+
+    for camera in discovered {
+        let data = CameraData::from(camera);
+        let status = data.status.clone().unwrap_or(CameraStatus::Offline);
+        let linked_camera = match linked
+            .iter()
+            .find(|linked| !matched.contains(&linked.id) && is_same_camera(linked, &data))
+        {
+            Some(linked_camera) => linked_camera,
+            None => {
+                let is_duplicate = linked
+                    .iter()
+                    .any(|linked| matched.contains(&linked.id) && is_same_camera(linked, &data));
+                if !is_duplicate {
+                    plan.statuses.push(CameraData {
+                        status: Some(status),
+                        mac_address: data.mac_address,
+                        uri: data.uri,
+                        ..Default::default()
+                    });
+                }
+                plan.unmatched.push(camera.clone());
+                continue;
+            }
+        };
+        matched.push(linked_camera.id);
+
+        if linked_camera.status != status {
+            plan.statuses.push(status_update(linked_camera, status));
+        }
+        if let Some(update) = detail_update(linked_camera, &data) {
+            plan.updates.push((linked_camera.id, update));
+        }
+
+        if let DiscoveredCamera::Remote(camera) = camera {
+            let registered: Vec<_> = streams
+                .iter()
+                .filter(|stream| stream.camera_id == Some(linked_camera.id))
+                .filter_map(|stream| stream.uri.as_ref())
+                .collect();
+            plan.streams.extend(
+                camera
+                    .stream_data(linked_camera.id, Some(gateway_id))
+                    .into_iter()
+                    .filter(|stream| !registered.contains(&&stream.uri)),
+            );
+        }
+    }
+
+    for camera in linked.iter().filter(|camera| !matched.contains(&camera.id)) {
+        if camera.status != CameraStatus::Offline {
+            plan.statuses.push(status_update(camera, CameraStatus::Offline));
+        }
+    }
+
+    plan
+}
+
+fn is_same_camera(linked: &Camera, discovered: &CameraData) -> bool {
+    match (&linked.mac_address, &discovered.mac_address) {
+        (Some(linked), Some(discovered)) => linked.eq_ignore_ascii_case(discovered),
+        _ => linked.uri.is_some() && linked.uri == discovered.uri,
+    }
+}
+
+fn status_update(camera: &Camera, status: CameraStatus) -> CameraData {
+    CameraData {
+        status: Some(status),
+        mac_address: camera.mac_address.clone(),
+        uri: camera.uri.clone(),
+        ..Default::default()
+    }
+}
+
+/// Returns the discovered details that differ from the linked camera, if any.
+fn detail_update(linked: &Camera, discovered: &CameraData) -> Option<CameraData> {
+    let update = CameraData {
+        uri: changed(&linked.uri, &discovered.uri),
+        mac_address: changed(&linked.mac_address, &discovered.mac_address),
+        model: changed(&linked.model, &discovered.model),
+        ip_local: changed(&linked.ip_local, &discovered.ip_local),
+        conn_type: changed(&linked.conn_type, &discovered.conn_type),
+        capabilities: changed(&linked.capabilities, &discovered.capabilities),
+        ..Default::default()
+    };
+
+    (update != CameraData::default()).then(|| update)
+}
+
+/// Returns the discovered `value` if it is known and differs from `field`.
+fn changed<T: Clone + PartialEq>(field: &Option<T>, value: &Option<T>) -> Option<T> {
+    value.as_ref().filter(|_| field != value).cloned()
+}
+
+impl GatewayClient<'_> {
+    /// Reconciles the cameras linked to the gateway with the cameras it `discovered`, and returns
+    /// the applied plan (see [`plan_reconciliation`]).
+    ///
+    /// Statuses are set first, then details are updated and streams are created. The first
+    /// failing request aborts the reconciliation.
+    pub async fn reconcile_cameras(
+        &self,
+        discovered: &[DiscoveredCamera],
+    ) -> Result<ReconcilePlan> {
+        let app = self.app();
+        let linked = self.list_linked_cameras().await?;
+
+        let mut streams = Vec::new();
+        for camera in &linked {
+            let has_streams = discovered.iter().any(|discovered| {
+                matches!(discovered, DiscoveredCamera::Remote(remote) if !remote.streams.is_empty())
+                    && is_same_camera(camera, &CameraData::from(discovered))
+            });
+            if has_streams {
+                streams.extend(app.list_camera_streams(camera.id).await?);
+            }
+        }
+
+        let plan = plan_reconciliation(self.gateway_id(), discovered, &linked, &streams);
+        if !plan.statuses.is_empty() {
+            self.set_cameras_statuses(&plan.statuses).await?;
+        }
+        for (camera_id, update) in &plan.updates {
+            app.update_camera(*camera_id, update).await?;
+        }
+        for stream in &plan.streams {
+            app.create_stream(stream).await?;
+        }
+
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cameras::ConnType,
+        commands::camera::{RemoteCamera, Status, Stream as DiscoveredStream},
+    };
+
+    const GATEWAY_ID: Uuid = Uuid::from_u128(1);
+
+    fn linked(id: u128, mac_address: &str, uri: &str, status: &str) -> Camera {
+        serde_json::from_value(json!({
+            "id": Uuid::from_u128(id),
+            "created_at": "2022-06-01T10:00:00Z",
+            "updated_at": "2022-06-01T10:00:00Z",
+            "application_id": Uuid::nil(),
+            "gateway_id": GATEWAY_ID,
+            "name": format!("Camera {id}"),
+            "status": status,
+            "conn_type": "remote",
+            "mac_address": mac_address,
+            "uri": uri,
+            "ip_local": "192.168.0.42",
+        }))
+        .unwrap()
+    }
+
+    fn remote(mac_address: &str, uri: &str, status: Status, streams: &[&str]) -> DiscoveredCamera {
+        DiscoveredCamera::Remote(RemoteCamera {
+            uri: uri.parse().unwrap(),
+            mac_address: mac_address.into(),
+            status,
+            name: Some("Discovered".into()),
+            manufacturer: None,
+            model: None,
+            ip_local: Some("192.168.0.42".into()),
+            streams: streams
+                .iter()
+                .map(|uri| DiscoveredStream {
+                    rtsp_uri: uri.parse().unwrap(),
+                    name: "Stream".into(),
+                    capability: serde_json::from_value(json!({
+                        "name": "1080p", "width": 1920, "height": 1080, "framerates": []
+                    }))
+                    .unwrap(),
+                })
+                .collect(),
+        })
+    }
+
+    fn stream(camera_id: u128, uri: &str) -> Stream {
+        serde_json::from_value(json!({
+            "id": Uuid::from_u128(100 + camera_id),
+            "created_at": "2022-06-01T10:00:00Z",
+            "updated_at": "2022-06-01T10:00:00Z",
+            "application_id": Uuid::nil(),
+            "name": "Stream",
+            "source": "camera_stream",
+            "stream_type": "rtsp",
+            "uri": uri,
+            "status": "online",
+            "camera_id": Uuid::from_u128(camera_id),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn unchanged_cameras_need_nothing() {
+        let linked = [linked(2, "00:11:22:33:44:55", "http://192.168.0.42/device", "online")];
+        let discovered = [remote(
+            "00:11:22:33:44:55",
+            "http://192.168.0.42/device",
+            Status::Online,
+            &["rtsp://192.168.0.42/hd"],
+        )];
+        let streams = [stream(2, "rtsp://192.168.0.42/hd")];
+
+        let plan = plan_reconciliation(GATEWAY_ID, &discovered, &linked, &streams);
+
+        assert!(plan.is_empty(), "{plan:?}");
+        assert!(plan.unmatched.is_empty());
+    }
+
+    #[test]
+    fn plans_minimal_changes() {
+        let linked = [
+            linked(2, "00:11:22:33:44:55", "http://192.168.0.42/device", "online"),
+            linked(3, "00:11:22:33:44:66", "http://192.168.0.43/device", "online"),
+            linked(4, "00:11:22:33:44:77", "http://192.168.0.44/device", "offline"),
+        ];
+        let discovered = [
+            // Moved to another IP address, with an additional stream.
+            remote(
+                "00:11:22:33:44:55",
+                "http://192.168.0.50/device",
+                Status::Unauthorized,
+                &["rtsp://192.168.0.50/hd", "rtsp://192.168.0.50/sd"],
+            ),
+            remote("00:11:22:33:44:88", "http://192.168.0.45/device", Status::Online, &[]),
+        ];
+        let streams = [stream(2, "rtsp://192.168.0.50/hd")];
+
+        let plan = plan_reconciliation(GATEWAY_ID, &discovered, &linked, &streams);
+
+        let statuses: Vec<_> =
+            plan.statuses.iter().map(|data| (data.mac_address.as_deref(), &data.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                (Some("00:11:22:33:44:55"), &Some(CameraStatus::Unauthorized)),
+                (Some("00:11:22:33:44:88"), &Some(CameraStatus::Online)),
+                (Some("00:11:22:33:44:66"), &Some(CameraStatus::Offline)),
+            ]
+        );
+
+        assert_eq!(plan.updates.len(), 1);
+        let (camera_id, update) = &plan.updates[0];
+        assert_eq!(*camera_id, Uuid::from_u128(2));
+        let uri = "http://192.168.0.50/device".parse().unwrap();
+        assert_eq!(*update, CameraData { uri: Some(uri), ..Default::default() });
+
+        assert_eq!(plan.streams.len(), 1);
+        assert_eq!(plan.streams[0].uri.as_str(), "rtsp://192.168.0.50/sd");
+        assert_eq!(plan.streams[0].camera_id, Some(Uuid::from_u128(2)));
+        assert_eq!(plan.streams[0].gateway_id, Some(GATEWAY_ID));
+
+        assert_eq!(plan.unmatched, [discovered[1].clone()]);
+    }
+
+    #[test]
+    fn reports_new_cameras() {
+        let linked = [linked(2, "00:11:22:33:44:55", "http://192.168.0.42/device", "online")];
+        let discovered = [
+            remote("00:11:22:33:44:55", "http://192.168.0.42/device", Status::Online, &[]),
+            remote("00:11:22:33:44:88", "http://192.168.0.45/device", Status::Online, &[]),
+        ];
+
+        let plan = plan_reconciliation(GATEWAY_ID, &discovered, &linked, &[]);
+
+        assert_eq!(plan.statuses.len(), 1);
+        assert_eq!(plan.statuses[0].mac_address.as_deref(), Some("00:11:22:33:44:88"));
+        assert_eq!(plan.statuses[0].status, Some(CameraStatus::Online));
+        assert_eq!(plan.unmatched, [discovered[1].clone()]);
+    }
+
+    #[test]
+    fn matches_linked_cameras_once() {
+        let linked = [linked(2, "00:11:22:33:44:55", "http://192.168.0.42/device", "online")];
+        let discovered = [
+            remote("00:11:22:33:44:55", "http://192.168.0.50/device", Status::Online, &[]),
+            remote("00:11:22:33:44:55", "http://192.168.0.51/device", Status::Online, &[]),
+        ];
+
+        let plan = plan_reconciliation(GATEWAY_ID, &discovered, &linked, &[]);
+
+        assert_eq!(plan.updates.len(), 1);
+        assert_eq!(plan.updates[0].1.uri.as_ref().unwrap().as_str(), "http://192.168.0.50/device");
+        assert_eq!(plan.unmatched, [discovered[1].clone()]);
+        assert!(plan.statuses.is_empty());
+    }
+
+    #[test]
+    fn matches_by_uri_without_mac_address() {
+        let mut camera = linked(2, "", "file:///dev/video0", "offline");
+        camera.mac_address = None;
+        camera.conn_type = Some(ConnType::Local);
+        let discovered: DiscoveredCamera = serde_json::from_value(json!({
+            "conn_type": "local",
+            "uri": "file:///dev/video0",
+            "status": "online",
+            "interface": "usb",
+            "capabilities": []
+        }))
+        .unwrap();
+
+        let plan = plan_reconciliation(GATEWAY_ID, &[discovered], &[camera], &[]);
+
+        assert_eq!(plan.statuses.len(), 1);
+        assert_eq!(plan.statuses[0].uri.as_ref().unwrap().as_str(), "file:///dev/video0");
+        assert!(plan.unmatched.is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn applies_plan() {
+        use crate::testing::{MockServer, Resource};
+
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let gateway_id = server.insert(
+            Resource::Gateways,
+            json!({ "application_id": application_id, "name": "Edge", "status": "online" }),
+        );
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let gateway = client.app(application_id).gateway(gateway_id);
+        let camera_id = server.insert(
+            Resource::Cameras,
+            json!({
+                "application_id": application_id,
+                "gateway_id": gateway_id,
+                "name": "Parking",
+                "status": "online",
+                "mac_address": "00:11:22:33:44:55",
+                "uri": "http://192.168.0.42/device",
+            }),
+        );
+        let discovered = [remote(
+            "00:11:22:33:44:55",
+            "http://192.168.0.50/device",
+            Status::Unauthorized,
+            &["rtsp://192.168.0.50/hd"],
+        )];
+
+        let plan = gateway.reconcile_cameras(&discovered).await.unwrap();
+        assert_eq!((plan.statuses.len(), plan.updates.len(), plan.streams.len()), (1, 1, 1));
+
+        let camera = client.read_camera(camera_id).await.unwrap();
+        assert_eq!(camera.status, CameraStatus::Unauthorized);
+        assert_eq!(camera.uri.unwrap().as_str(), "http://192.168.0.50/device");
+        assert_eq!(camera.name, "Parking");
+        assert_eq!(client.list_camera_streams(camera_id).await.unwrap().len(), 1);
+
+        // Everything is up to date now.
+        assert!(gateway.reconcile_cameras(&discovered).await.unwrap().is_empty());
+    }
+}
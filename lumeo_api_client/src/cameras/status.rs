@@ -0,0 +1,112 @@
+use std::{convert::Infallible, fmt, str::FromStr};
+
+use serde::{
+    de::{Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+
+use crate::commands::camera::Status;
+
+/// Status of a camera record.
+///
+/// Statuses introduced by newer API versions are kept as `Unknown`, so that they survive a round
+/// trip through the client.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CameraStatus {
+    Online,
+    Offline,
+    Unauthorized,
+    Unknown(String),
+}
+
+impl CameraStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CameraStatus::Online => "online",
+            CameraStatus::Offline => "offline",
+            CameraStatus::Unauthorized => "unauthorized",
+            CameraStatus::Unknown(status) => status,
+        }
+    }
+}
+
+impl FromStr for CameraStatus {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "online" => CameraStatus::Online,
+            "offline" => CameraStatus::Offline,
+            "unauthorized" => CameraStatus::Unauthorized,
+            status => CameraStatus::Unknown(status.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for CameraStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Status> for CameraStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Online => CameraStatus::Online,
+            Status::Offline => CameraStatus::Offline,
+            Status::Unauthorized => CameraStatus::Unauthorized,
+        }
+    }
+}
+
+impl TryFrom<CameraStatus> for Status {
+    /// The unknown status
+    type Error = String;
+
+    fn try_from(status: CameraStatus) -> Result<Self, Self::Error> {
+        match status {
+            CameraStatus::Online => Ok(Status::Online),
+            CameraStatus::Offline => Ok(Status::Offline),
+            CameraStatus::Unauthorized => Ok(Status::Unauthorized),
+            CameraStatus::Unknown(status) => Err(status),
+        }
+    }
+}
+
+impl Serialize for CameraStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for CameraStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keeps_unknown_statuses() {
+        let status: CameraStatus = serde_json::from_value(json!("unauthorized")).unwrap();
+        assert_eq!(status, CameraStatus::Unauthorized);
+
+        let status: CameraStatus = serde_json::from_value(json!("rebooting")).unwrap();
+        assert_eq!(status, CameraStatus::Unknown("rebooting".into()));
+        assert_eq!(serde_json::to_value(&status).unwrap(), json!("rebooting"));
+        assert_eq!(Status::try_from(status), Err("rebooting".to_owned()));
+    }
+}
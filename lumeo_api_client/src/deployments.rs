@@ -1,6 +1,7 @@
-use std::{collections::BTreeMap, fmt};
+use std::fmt;
 
 use chrono::{DateTime, Utc};
+use futures::{future, stream, Stream, StreamExt};
 use serde::{
     de::{self, value::SeqAccessDeserializer, Deserializer, Visitor},
     Deserialize, Serialize,
@@ -9,7 +10,21 @@ use serde_with::skip_serializing_none;
 use uuid::Uuid;
 
 use super::Client;
-use crate::{pipeline::Pipeline, Result};
+use crate::{
+    pagination::{paginate, Paginated},
+    pipeline::{Configuration, Pipeline, UpdatePlan},
+    AppClient, Result,
+};
+
+mod bulk;
+mod logs;
+mod state;
+mod watch;
+
+pub use bulk::{BulkReport, Selection, DEFAULT_BULK_CONCURRENCY};
+pub use logs::{DeploymentErrorEvent, DeploymentLogs};
+pub use state::Action;
+pub use watch::StateChange;
 
 #[skip_serializing_none]
 #[derive(Debug, Deserialize)]
@@ -25,6 +40,16 @@ pub struct Deployment {
     pub definition: Pipeline,
 }
 
+impl Paginated for Deployment {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
 #[derive(Serialize)]
 pub struct NewDeployment {
     pub pipeline_id: Uuid,
@@ -33,7 +58,7 @@ pub struct NewDeployment {
     pub data: DeploymentData,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct DeploymentData {
     pub name: Option<String>,
     pub state: Option<State>,
@@ -41,9 +66,21 @@ pub struct DeploymentData {
     pub configuration: Option<DeploymentConfiguration>,
 }
 
-pub type DeploymentConfiguration = BTreeMap<String, serde_json::Map<String, serde_json::Value>>;
+/// Property overrides of the deployment's pipeline, see [`Pipeline::configure`].
+pub type DeploymentConfiguration = Configuration;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, strum::AsRefStr)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    strum::AsRefStr,
+    strum::Display,
+    strum::EnumIter,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum State {
@@ -56,9 +93,9 @@ pub enum State {
     Unknown,
 }
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct ListParams {
-    /// Maximum number of deployments to return
+    /// Maximum number of deployments to return (page size for [`AppClient::get_deployments_stream`])
     pub limit: i16,
     /// Filter: Lower bound for creation time (inclusive)
     pub created_ts_since: Option<DateTime<Utc>>,
@@ -76,25 +113,52 @@ pub struct ListParams {
     pub states: Vec<State>,
 }
 
-impl Client {
+impl<'a> AppClient<'a> {
     pub async fn get_deployments(&self, filter: &ListParams) -> Result<Vec<Deployment>> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments");
-        self.get(&path, Some(&filter)).await
+        self.client().get(&path, Some(&filter)).await
+    }
+
+    /// Returns all deployments matching `filter`, newest first, fetching them page by page.
+    pub fn get_deployments_stream(
+        self,
+        filter: ListParams,
+    ) -> impl Stream<Item = Result<Deployment>> + 'a {
+        paginate(filter.created_ts_until, filter.limit, move |created_ts_until, limit| {
+            let filter = ListParams { created_ts_until, limit, ..filter.clone() };
+            async move { self.get_deployments(&filter).await }
+        })
+    }
+
+    /// Returns the deployments matching `filter` that were updated since `updated_ts_since`
+    /// (inclusive), newest first, fetching them page by page.
+    ///
+    /// Pass the highest `updated_at` returned as `updated_ts_since` of the next call to only get
+    /// later updates. Deployments updated at exactly that time are returned again.
+    pub fn get_deployment_updates_stream(
+        self,
+        updated_ts_since: DateTime<Utc>,
+        filter: ListParams,
+    ) -> impl Stream<Item = Result<Deployment>> + 'a {
+        self.get_deployments_stream(ListParams {
+            updated_ts_since: Some(updated_ts_since),
+            ..filter
+        })
     }
 
     pub async fn create_deployment(&self, data: &NewDeployment) -> Result<Deployment> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments");
-        self.post(&path, data).await
+        self.client().post(&path, data).await
     }
 
     // FIXME: Make method naming consistent for all methods. It is either create/read/update/delete
     //        or post/get/put/delete.
     pub async fn get_deployment(&self, deployment_id: Uuid) -> Result<Deployment> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}");
-        self.get(&path, None::<&()>).await
+        self.client().get(&path, None::<&()>).await
     }
 
     pub async fn update_deployment(
@@ -102,33 +166,141 @@ impl Client {
         deployment_id: Uuid,
         data: &DeploymentData,
     ) -> Result<Deployment> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}");
-        self.put(&path, data).await
+        self.client().put(&path, data).await
     }
 
     pub async fn delete_deployment(&self, deployment_id: Uuid) -> Result<()> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}");
-        self.delete(&path, None::<&()>).await
+        self.client().delete(&path, None::<&()>).await
     }
 
     pub async fn get_deployment_definition(&self, deployment_id: Uuid) -> Result<Pipeline> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}/definition");
-        self.get(&path, None::<&()>).await
+        self.client().get(&path, None::<&()>).await
+    }
+
+    /// Compares the current definition of the deployment with `pipeline`, see
+    /// [`Pipeline::plan_update`].
+    pub async fn plan_deployment_update(
+        &self,
+        deployment_id: Uuid,
+        pipeline: &Pipeline,
+    ) -> Result<UpdatePlan> {
+        let definition = self.get_deployment_definition(deployment_id).await?;
+        Ok(definition.plan_update(pipeline))
     }
 
     pub async fn start_deployment(&self, deployment_id: Uuid) -> Result<()> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}/start");
-        self.post_without_response_deserialization(&path, None::<&()>).await
+        self.client().post_without_response_deserialization(&path, None::<&()>).await
     }
 
     pub async fn stop_deployment(&self, deployment_id: Uuid) -> Result<()> {
-        let application_id = self.application_id()?;
+        let application_id = self.application_id();
         let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}/stop");
-        self.post_without_response_deserialization(&path, None::<&()>).await
+        self.client().post_without_response_deserialization(&path, None::<&()>).await
+    }
+
+    /// Starts the deployment, failing with
+    /// [`Error::IllegalDeploymentAction`](crate::error::Error::IllegalDeploymentAction) without
+    /// sending the request if it can't be started in its current state.
+    ///
+    /// This fetches the deployment first. Its state may still change before the start request is
+    /// handled, so the check can't replace handling errors of the request.
+    pub async fn checked_start_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.check_action(deployment_id, Action::Start).await?;
+        self.start_deployment(deployment_id).await
+    }
+
+    /// Stops the deployment, failing with
+    /// [`Error::IllegalDeploymentAction`](crate::error::Error::IllegalDeploymentAction) without
+    /// sending the request if it can't be stopped in its current state, see
+    /// [`checked_start_deployment`](Self::checked_start_deployment).
+    pub async fn checked_stop_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.check_action(deployment_id, Action::Stop).await?;
+        self.stop_deployment(deployment_id).await
+    }
+}
+
+impl Client {
+    pub async fn get_deployments(&self, filter: &ListParams) -> Result<Vec<Deployment>> {
+        self.default_app()?.get_deployments(filter).await
+    }
+
+    /// Returns all deployments matching `filter`, newest first, fetching them page by page.
+    pub fn get_deployments_stream(
+        &self,
+        filter: ListParams,
+    ) -> impl Stream<Item = Result<Deployment>> + '_ {
+        match self.default_app() {
+            Ok(app) => app.get_deployments_stream(filter).left_stream(),
+            Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+        }
+    }
+
+    /// See [`AppClient::get_deployment_updates_stream`].
+    pub fn get_deployment_updates_stream(
+        &self,
+        updated_ts_since: DateTime<Utc>,
+        filter: ListParams,
+    ) -> impl Stream<Item = Result<Deployment>> + '_ {
+        match self.default_app() {
+            Ok(app) => app.get_deployment_updates_stream(updated_ts_since, filter).left_stream(),
+            Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+        }
+    }
+
+    pub async fn create_deployment(&self, data: &NewDeployment) -> Result<Deployment> {
+        self.default_app()?.create_deployment(data).await
+    }
+
+    pub async fn get_deployment(&self, deployment_id: Uuid) -> Result<Deployment> {
+        self.default_app()?.get_deployment(deployment_id).await
+    }
+
+    pub async fn update_deployment(
+        &self,
+        deployment_id: Uuid,
+        data: &DeploymentData,
+    ) -> Result<Deployment> {
+        self.default_app()?.update_deployment(deployment_id, data).await
+    }
+
+    pub async fn delete_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.default_app()?.delete_deployment(deployment_id).await
+    }
+
+    pub async fn get_deployment_definition(&self, deployment_id: Uuid) -> Result<Pipeline> {
+        self.default_app()?.get_deployment_definition(deployment_id).await
+    }
+
+    pub async fn plan_deployment_update(
+        &self,
+        deployment_id: Uuid,
+        pipeline: &Pipeline,
+    ) -> Result<UpdatePlan> {
+        self.default_app()?.plan_deployment_update(deployment_id, pipeline).await
+    }
+
+    pub async fn start_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.default_app()?.start_deployment(deployment_id).await
+    }
+
+    pub async fn stop_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.default_app()?.stop_deployment(deployment_id).await
+    }
+
+    pub async fn checked_start_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.default_app()?.checked_start_deployment(deployment_id).await
+    }
+
+    pub async fn checked_stop_deployment(&self, deployment_id: Uuid) -> Result<()> {
+        self.default_app()?.checked_stop_deployment(deployment_id).await
     }
 }
 
@@ -0,0 +1,190 @@
+use std::future::Future;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use uuid::Uuid;
+
+use super::ListParams;
+use crate::{error::Error, AppClient, Client, Result};
+
+/// Number of requests bulk operations run concurrently by default.
+pub const DEFAULT_BULK_CONCURRENCY: usize = 8;
+
+/// Deployments a bulk operation applies to.
+#[derive(Clone, Debug)]
+pub enum Selection {
+    Ids(Vec<Uuid>),
+    /// All deployments matching the filter, fetched page by page
+    Filter(ListParams),
+}
+
+impl From<Vec<Uuid>> for Selection {
+    fn from(ids: Vec<Uuid>) -> Self {
+        Self::Ids(ids)
+    }
+}
+
+impl From<ListParams> for Selection {
+    fn from(filter: ListParams) -> Self {
+        Self::Filter(filter)
+    }
+}
+
+/// Outcome of a bulk operation for each selected deployment.
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    /// Results in the order the deployments were selected in
+    pub results: Vec<(Uuid, Result<()>)>,
+}
+
+impl BulkReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.results.iter().filter(|(_, result)| result.is_ok()).map(|(id, _)| *id)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (Uuid, &Error)> + '_ {
+        self.results.iter().filter_map(|(id, result)| Some((*id, result.as_ref().err()?)))
+    }
+
+    /// Whether the operation succeeded for every deployment.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+}
+
+impl AppClient<'_> {
+    /// Starts the selected deployments, running up to `concurrency` requests at once.
+    ///
+    /// Fails only if the deployments matching a filter can't be listed, failures of individual
+    /// deployments are reported in the [`BulkReport`].
+    pub async fn start_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.bulk(selection.into(), concurrency, |id| self.start_deployment(id)).await
+    }
+
+    /// Stops the selected deployments, see [`start_deployments`](Self::start_deployments).
+    pub async fn stop_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.bulk(selection.into(), concurrency, |id| self.stop_deployment(id)).await
+    }
+
+    /// Deletes the selected deployments, see [`start_deployments`](Self::start_deployments).
+    pub async fn delete_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.bulk(selection.into(), concurrency, |id| self.delete_deployment(id)).await
+    }
+
+    async fn bulk<F, Fut>(
+        &self,
+        selection: Selection,
+        concurrency: usize,
+        operation: F,
+    ) -> Result<BulkReport>
+    where
+        F: Fn(Uuid) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let ids = match selection {
+            Selection::Ids(ids) => ids,
+            Selection::Filter(filter) => {
+                self.get_deployments_stream(filter).map_ok(|d| d.id).try_collect().await?
+            }
+        };
+
+        let results = stream::iter(ids)
+            .map(|id| {
+                let operation = operation(id);
+                async move { (id, operation.await) }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        Ok(BulkReport { results })
+    }
+}
+
+impl Client {
+    pub async fn start_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.default_app()?.start_deployments(selection, concurrency).await
+    }
+
+    pub async fn stop_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.default_app()?.stop_deployments(selection, concurrency).await
+    }
+
+    pub async fn delete_deployments(
+        &self,
+        selection: impl Into<Selection>,
+        concurrency: usize,
+    ) -> Result<BulkReport> {
+        self.default_app()?.delete_deployments(selection, concurrency).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        deployments::State,
+        testing::{MockServer, Resource},
+    };
+
+    #[tokio::test]
+    async fn reports_partial_failures() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+
+        let mut ids: Vec<_> = [State::Stopped, State::Error]
+            .iter()
+            .map(|state| {
+                server.insert(
+                    Resource::Deployments,
+                    json!({
+                        "application_id": application_id,
+                        "name": format!("Deployment {state}"),
+                        "pipeline_id": Uuid::new_v4(),
+                        "gateway_id": Uuid::new_v4(),
+                        "state": state,
+                        "definition": "[]",
+                    }),
+                )
+            })
+            .collect();
+        let missing = Uuid::new_v4();
+        ids.push(missing);
+
+        let report = client.start_deployments(ids.clone(), 2).await.unwrap();
+
+        assert!(!report.is_success());
+        assert_eq!(report.results.iter().map(|(id, _)| *id).collect::<Vec<_>>(), ids);
+        assert_eq!(report.succeeded().collect::<Vec<_>>(), [ids[0], ids[1]]);
+        let failed: Vec<_> = report.failed().collect();
+        assert!(matches!(failed[..], [(id, err)] if id == missing && err.is_not_found()));
+        // One request per deployment.
+        assert_eq!(server.requests().len(), ids.len());
+
+        let report = client.delete_deployments(ids[..2].to_vec(), DEFAULT_BULK_CONCURRENCY).await;
+        assert!(report.unwrap().is_success());
+        assert!(server.records(Resource::Deployments).is_empty());
+    }
+}
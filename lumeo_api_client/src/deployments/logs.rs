@@ -0,0 +1,114 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    events::{self, DeploymentError, ErrorData, Event, Severity},
+    AppClient, Client, Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct DeploymentLogs {
+    pub logs: String,
+}
+
+/// An error event of a deployment.
+#[derive(Debug)]
+pub struct DeploymentErrorEvent {
+    pub event: Event,
+    /// `None` if the payload of the event isn't a known deployment error
+    pub error: Option<DeploymentError>,
+}
+
+impl AppClient<'_> {
+    pub async fn get_deployment_logs(&self, deployment_id: Uuid) -> Result<DeploymentLogs> {
+        let application_id = self.application_id();
+        let path = format!("/v1/apps/{application_id}/deployments/{deployment_id}/logs");
+        self.client().get(&path, None::<&()>).await
+    }
+
+    /// Returns up to `limit` of the most recent error events of the deployment, newest first.
+    pub async fn get_deployment_errors(
+        &self,
+        deployment_id: Uuid,
+        limit: i16,
+    ) -> Result<Vec<DeploymentErrorEvent>> {
+        let params = events::ListParams {
+            limit,
+            object_ids: vec![deployment_id],
+            severities: vec![Severity::Error],
+            ..Default::default()
+        };
+        let events = self.list_events(&params).await?;
+
+        Ok(events
+            .into_iter()
+            .map(|event| {
+                let error = event.error_data().map(|ErrorData::Deployment { error, .. }| error);
+                DeploymentErrorEvent { event, error }
+            })
+            .collect())
+    }
+}
+
+impl Client {
+    pub async fn get_deployment_logs(&self, deployment_id: Uuid) -> Result<DeploymentLogs> {
+        self.default_app()?.get_deployment_logs(deployment_id).await
+    }
+
+    pub async fn get_deployment_errors(
+        &self,
+        deployment_id: Uuid,
+        limit: i16,
+    ) -> Result<Vec<DeploymentErrorEvent>> {
+        self.default_app()?.get_deployment_errors(deployment_id, limit).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        events::GstErrorDomain,
+        testing::{MockServer, Resource},
+    };
+
+    #[tokio::test]
+    async fn fetches_logs_and_errors() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let deployment_id = server.insert(
+            Resource::Deployments,
+            json!({
+                "application_id": application_id,
+                "name": "Entrance",
+                "pipeline_id": Uuid::new_v4(),
+                "gateway_id": Uuid::new_v4(),
+                "state": "error",
+                "definition": "[]",
+                "logs": "Pipeline failed to start\n",
+            }),
+        );
+
+        let logs = client.get_deployment_logs(deployment_id).await.unwrap();
+        assert_eq!(logs.logs, "Pipeline failed to start\n");
+
+        let error = DeploymentError::GstError { domain: GstErrorDomain::Resource, code: 3 };
+        let error_data = ErrorData::Deployment { deployment_id, error: error.clone() };
+        client.create_error_event(&error_data).await.unwrap();
+        let other_deployment =
+            ErrorData::Deployment { deployment_id: Uuid::new_v4(), error: error.clone() };
+        client.create_error_event(&other_deployment).await.unwrap();
+
+        let errors = client.get_deployment_errors(deployment_id, 10).await.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event.object_id, Some(deployment_id));
+        assert_eq!(errors[0].error, Some(error));
+        assert_eq!(
+            errors[0].error.as_ref().unwrap().to_string(),
+            "GStreamer error GST_RESOURCE_ERROR_NOT_FOUND"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Transitions between deployment states.
+//!
+//! ```text
+//!              start                     ┌──────────► Running ───┐
+//! Stopped ──────────► Deploying ─────────┤                       │ stop
+//!    ▲                                   └──► Error/Interrupted  ▼
+//!    └───────────────────────────────────────────────────── Stopping
+//! ```
+//!
+//! The gateway reports `Error` and `Interrupted` from any active state, and `Unknown` when it
+//! can't tell. Any state may follow `Unknown`.
+
+use strum::Display;
+use uuid::Uuid;
+
+use super::{DeploymentConfiguration, DeploymentData, State};
+use crate::{
+    error::Error,
+    pipeline::{Configuration, Pipeline},
+    AppClient, Result,
+};
+
+/// An action requested by a client, changing the state of a deployment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Action {
+    Start,
+    Stop,
+}
+
+impl State {
+    /// Whether the deployment stays in this state until it is started again.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, State::Stopped | State::Interrupted | State::Error)
+    }
+
+    /// Whether a deployment in this state can go to state `next`.
+    pub fn can_transition_to(self, next: State) -> bool {
+        use State::{Deploying, Error, Interrupted, Running, Stopped, Stopping, Unknown};
+
+        match (self, next) {
+            (current, next) if current == next => false,
+            (Unknown, _) | (_, Unknown) => true,
+            (Deploying, Running | Stopping | Error | Interrupted) => true,
+            // Running deployments are redeployed when their definition is updated.
+            (Running, Deploying | Stopping | Error | Interrupted) => true,
+            (Stopping, Stopped | Error) => true,
+            (Stopped, Deploying) => true,
+            (Interrupted, Deploying | Running | Stopping | Stopped | Error) => true,
+            (Error, Deploying | Stopped) => true,
+            _ => false,
+        }
+    }
+
+    /// The state a deployment goes to when started, or `None` if it can't be started.
+    pub fn next_on_start(self) -> Option<State> {
+        match self {
+            State::Stopped | State::Interrupted | State::Error | State::Unknown => {
+                Some(State::Deploying)
+            }
+            State::Deploying | State::Running | State::Stopping => None,
+        }
+    }
+
+    /// The state a deployment goes to when stopped, or `None` if it can't be stopped.
+    pub fn next_on_stop(self) -> Option<State> {
+        match self {
+            State::Deploying | State::Running | State::Interrupted | State::Unknown => {
+                Some(State::Stopping)
+            }
+            State::Stopping | State::Stopped | State::Error => None,
+        }
+    }
+
+    /// The state a deployment goes to when `action` is applied.
+    pub fn next_on(self, action: Action) -> Option<State> {
+        match action {
+            Action::Start => self.next_on_start(),
+            Action::Stop => self.next_on_stop(),
+        }
+    }
+}
+
+impl DeploymentData {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Serialized pipeline definition
+    pub fn definition(mut self, definition: impl Into<String>) -> Self {
+        self.definition = Some(definition.into());
+        self
+    }
+
+    pub fn configuration(mut self, configuration: DeploymentConfiguration) -> Self {
+        self.configuration = Some(configuration);
+        self
+    }
+
+    /// Sets the definition to `pipeline` with `configuration` applied.
+    ///
+    /// Fails with [`Error::InvalidConfiguration`] if the configuration doesn't match the pipeline,
+    /// see [`Pipeline::configure`].
+    pub fn configured_definition(
+        mut self,
+        pipeline: &Pipeline,
+        configuration: &Configuration,
+    ) -> Result<Self> {
+        let pipeline = pipeline.configure(configuration).map_err(Error::InvalidConfiguration)?;
+        self.definition = Some(serde_json::to_string(&pipeline).expect("Failed to serialize JSON"));
+        Ok(self)
+    }
+
+    /// Sets the state of a deployment currently in state `current`.
+    ///
+    /// Fails with [`Error::IllegalStateTransition`] if it can't go from `current` to `next`.
+    pub fn transition(mut self, current: State, next: State) -> Result<Self> {
+        if !current.can_transition_to(next) {
+            return Err(Error::IllegalStateTransition { from: current, to: next });
+        }
+
+        self.state = Some(next);
+        Ok(self)
+    }
+}
+
+impl AppClient<'_> {
+    /// Fetches the state of the deployment and checks that `action` can be applied to it.
+    pub(super) async fn check_action(&self, deployment_id: Uuid, action: Action) -> Result<()> {
+        let state = self.get_deployment(deployment_id).await?.state;
+        match state.next_on(action) {
+            Some(_) => Ok(()),
+            None => Err(self.client().through_cb(Error::IllegalDeploymentAction {
+                deployment_id,
+                action,
+                state,
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    const LEGAL: &[(State, State)] = {
+        use State::{Deploying, Error, Interrupted, Running, Stopped, Stopping};
+        &[
+            (Deploying, Running),
+            (Deploying, Stopping),
+            (Deploying, Error),
+            (Deploying, Interrupted),
+            (Running, Deploying),
+            (Running, Stopping),
+            (Running, Error),
+            (Running, Interrupted),
+            (Stopping, Stopped),
+            (Stopping, Error),
+            (Stopped, Deploying),
+            (Interrupted, Deploying),
+            (Interrupted, Running),
+            (Interrupted, Stopping),
+            (Interrupted, Stopped),
+            (Interrupted, Error),
+            (Error, Deploying),
+            (Error, Stopped),
+        ]
+    };
+
+    #[test]
+    fn transitions() {
+        for current in State::iter() {
+            for next in State::iter() {
+                let expected = current != next
+                    && (current == State::Unknown
+                        || next == State::Unknown
+                        || LEGAL.contains(&(current, next)));
+                assert_eq!(
+                    current.can_transition_to(next),
+                    expected,
+                    "transition from {current} to {next}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn actions_lead_to_legal_transitions() {
+        for current in State::iter() {
+            for action in [Action::Start, Action::Stop] {
+                if let Some(next) = current.next_on(action) {
+                    assert!(current.can_transition_to(next), "{action} in state {current}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn actions() {
+        let startable: Vec<_> = State::iter().filter(|s| s.next_on_start().is_some()).collect();
+        assert_eq!(startable, [State::Stopped, State::Interrupted, State::Error, State::Unknown]);
+
+        let stoppable: Vec<_> = State::iter().filter(|s| s.next_on_stop().is_some()).collect();
+        assert_eq!(
+            stoppable,
+            [State::Deploying, State::Running, State::Interrupted, State::Unknown]
+        );
+    }
+
+    #[test]
+    fn terminal_states() {
+        let terminal: Vec<_> = State::iter().filter(|s| s.is_terminal()).collect();
+        assert_eq!(terminal, [State::Stopped, State::Interrupted, State::Error]);
+    }
+
+    #[test]
+    fn builder_checks_transition() {
+        let data = DeploymentData::default()
+            .name("Entrance")
+            .transition(State::Stopped, State::Deploying)
+            .unwrap();
+        assert_eq!(data.name.as_deref(), Some("Entrance"));
+        assert_eq!(data.state, Some(State::Deploying));
+
+        let err =
+            DeploymentData::default().transition(State::Stopping, State::Running).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::IllegalStateTransition { from: State::Stopping, to: State::Running }
+        ));
+    }
+
+    #[test]
+    fn builder_checks_configuration() {
+        let pipeline: Pipeline = serde_json::from_str(
+            r#"[{ "id": "encode1", "properties": { "type": "encode", "codec": "h264" }, "wires": {} }]"#,
+        )
+        .unwrap();
+        let mut configuration = Configuration::new();
+        configuration.set("encode1", "codec", "h265");
+
+        let data = DeploymentData::default().configured_definition(&pipeline, &configuration);
+        let definition: Pipeline =
+            serde_json::from_str(&data.unwrap().definition.unwrap()).unwrap();
+        assert_eq!(definition, pipeline.configure(&configuration).unwrap());
+
+        configuration.set("encode2", "codec", "h265");
+        let err = DeploymentData::default().configured_definition(&pipeline, &configuration);
+        assert!(matches!(err.unwrap_err(), Error::InvalidConfiguration(_)));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn guards_start_and_stop() {
+        use serde_json::json;
+
+        use crate::testing::{MockServer, Resource};
+
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let deployment_id = server.insert(
+            Resource::Deployments,
+            json!({
+                "application_id": application_id,
+                "name": "Entrance",
+                "pipeline_id": Uuid::new_v4(),
+                "gateway_id": Uuid::new_v4(),
+                "state": "stopping",
+                "definition": "[]",
+            }),
+        );
+
+        let err = client.checked_stop_deployment(deployment_id).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::IllegalDeploymentAction { action: Action::Stop, state: State::Stopping, .. }
+        ));
+        assert!(client.checked_start_deployment(deployment_id).await.is_err());
+        assert!(server.requests().iter().all(|request| request.method == reqwest::Method::GET));
+
+        // Unchecked calls are sent as is.
+        client.stop_deployment(deployment_id).await.unwrap();
+        assert_eq!(server.requests().len(), 3);
+    }
+}
@@ -0,0 +1,284 @@
+use std::time::Duration;
+
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use uuid::Uuid;
+
+use super::{Deployment, State};
+use crate::{error::Error, AppClient, Client, Result};
+
+/// Time between two polls of a watched deployment.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change of the state of a watched deployment.
+#[derive(Debug)]
+pub struct StateChange {
+    /// `None` for the state observed when watching started
+    pub previous: Option<State>,
+    pub deployment: Deployment,
+}
+
+impl StateChange {
+    pub fn state(&self) -> State {
+        self.deployment.state
+    }
+
+    /// Whether a wait for another state must fail: the deployment went to a terminal state
+    /// after the wait began. `Interrupted` deployments may still recover.
+    fn fails_wait(&self) -> bool {
+        self.previous.is_some() && self.state().is_terminal() && self.state() != State::Interrupted
+    }
+}
+
+struct Watcher {
+    previous: Option<State>,
+    done: bool,
+    /// Whether the stream ends once the deployment is in a terminal state
+    ends_on_terminal: bool,
+}
+
+impl<'a> AppClient<'a> {
+    /// Polls the deployment and returns its state each time it changes, starting with the current
+    /// one.
+    ///
+    /// The stream ends once the deployment is in a [terminal](State::is_terminal) state, which may
+    /// be the current one, or after a request failed.
+    pub fn watch_deployment(
+        self,
+        deployment_id: Uuid,
+    ) -> impl Stream<Item = Result<StateChange>> + 'a {
+        self.state_changes(deployment_id, true)
+    }
+
+    fn state_changes(
+        self,
+        deployment_id: Uuid,
+        ends_on_terminal: bool,
+    ) -> impl Stream<Item = Result<StateChange>> + 'a {
+        let watcher = Watcher { previous: None, done: false, ends_on_terminal };
+
+        stream::try_unfold(watcher, move |mut watcher| async move {
+            if watcher.done {
+                return Ok(None);
+            }
+
+            loop {
+                if watcher.previous.is_some() {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+
+                let deployment = self.get_deployment(deployment_id).await?;
+                if watcher.previous != Some(deployment.state) {
+                    let change = StateChange { previous: watcher.previous, deployment };
+                    watcher.previous = Some(change.state());
+                    watcher.done = watcher.ends_on_terminal && change.state().is_terminal();
+                    return Ok(Some((change, watcher)));
+                }
+            }
+        })
+    }
+
+    /// Waits until the deployment is in state `target` and returns it.
+    ///
+    /// Fails with [`Error::UnexpectedDeploymentState`] as soon as the deployment goes to another
+    /// [terminal](State::is_terminal) state after the wait began, e.g. `Error` while waiting for
+    /// `Running`. The state observed first never fails the wait, so it can be called right after
+    /// requesting a start, while the deployment may still be `Stopped`. `Interrupted`
+    /// deployments may recover and are waited for. Fails with [`Error::DeploymentStateTimeout`]
+    /// if `target` isn't reached within `timeout`.
+    pub async fn wait_for_deployment_state(
+        &self,
+        deployment_id: Uuid,
+        target: State,
+        timeout: Duration,
+    ) -> Result<Deployment> {
+        let mut last = None;
+
+        let wait = async {
+            let mut changes = Box::pin(self.state_changes(deployment_id, false));
+            while let Some(change) = changes.try_next().await? {
+                last = Some(change.state());
+                if change.state() == target {
+                    return Ok(change.deployment);
+                }
+                if change.fails_wait() {
+                    break;
+                }
+            }
+
+            let state = last.unwrap_or(State::Unknown);
+            Err(Error::UnexpectedDeploymentState { deployment_id, target, state })
+        };
+        let result = tokio::time::timeout(timeout, wait).await;
+
+        result.unwrap_or(Err(Error::DeploymentStateTimeout {
+            deployment_id,
+            target,
+            last,
+            timeout,
+        }))
+    }
+}
+
+impl Client {
+    pub fn watch_deployment(
+        &self,
+        deployment_id: Uuid,
+    ) -> impl Stream<Item = Result<StateChange>> + '_ {
+        match self.default_app() {
+            Ok(app) => app.watch_deployment(deployment_id).left_stream(),
+            Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+        }
+    }
+
+    pub async fn wait_for_deployment_state(
+        &self,
+        deployment_id: Uuid,
+        target: State,
+        timeout: Duration,
+    ) -> Result<Deployment> {
+        self.default_app()?.wait_for_deployment_state(deployment_id, target, timeout).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{MockServer, Resource};
+
+    async fn setup(state: State) -> (MockServer, Client, Uuid) {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let deployment_id = server.insert(
+            Resource::Deployments,
+            json!({
+                "application_id": application_id,
+                "name": "Entrance",
+                "pipeline_id": Uuid::new_v4(),
+                "gateway_id": Uuid::new_v4(),
+                "state": state,
+                "definition": "[]",
+            }),
+        );
+        (server, client, deployment_id)
+    }
+
+    fn set_state(server: &MockServer, deployment_id: Uuid, state: State) {
+        let mut deployment = server.record(Resource::Deployments, deployment_id).unwrap();
+        deployment["state"] = json!(state);
+        server.insert(Resource::Deployments, deployment);
+    }
+
+    #[tokio::test]
+    async fn waits_for_target_state() {
+        let (server, client, deployment_id) = setup(State::Deploying).await;
+
+        let wait = client.wait_for_deployment_state(
+            deployment_id,
+            State::Running,
+            Duration::from_secs(10),
+        );
+        let update = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            set_state(&server, deployment_id, State::Running);
+        };
+        let (deployment, ()) = futures::join!(wait, update);
+
+        assert_eq!(deployment.unwrap().state, State::Running);
+    }
+
+    #[tokio::test]
+    async fn waits_past_initial_terminal_state() {
+        let (server, client, deployment_id) = setup(State::Stopped).await;
+
+        let wait = client.wait_for_deployment_state(
+            deployment_id,
+            State::Running,
+            Duration::from_secs(10),
+        );
+        let update = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            set_state(&server, deployment_id, State::Deploying);
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            set_state(&server, deployment_id, State::Running);
+        };
+        let (deployment, ()) = futures::join!(wait, update);
+
+        assert_eq!(deployment.unwrap().state, State::Running);
+
+        set_state(&server, deployment_id, State::Error);
+        let err = client
+            .wait_for_deployment_state(deployment_id, State::Running, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::DeploymentStateTimeout { last: Some(State::Error), .. }));
+    }
+
+    #[tokio::test]
+    async fn waits_for_interrupted_deployment() {
+        let (server, client, deployment_id) = setup(State::Deploying).await;
+
+        let wait = client.wait_for_deployment_state(
+            deployment_id,
+            State::Running,
+            Duration::from_secs(10),
+        );
+        let update = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            set_state(&server, deployment_id, State::Interrupted);
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            set_state(&server, deployment_id, State::Running);
+        };
+        let (deployment, ()) = futures::join!(wait, update);
+
+        assert_eq!(deployment.unwrap().state, State::Running);
+    }
+
+    #[tokio::test]
+    async fn fails_fast_on_terminal_state() {
+        let (server, client, deployment_id) = setup(State::Deploying).await;
+
+        let wait = client.wait_for_deployment_state(
+            deployment_id,
+            State::Running,
+            Duration::from_secs(10),
+        );
+        let update = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            set_state(&server, deployment_id, State::Error);
+        };
+        let (result, ()) = futures::join!(wait, update);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::UnexpectedDeploymentState { state: State::Error, target: State::Running, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn watch_ends_after_terminal_state() {
+        let (server, client, deployment_id) = setup(State::Running).await;
+
+        let watch = client.watch_deployment(deployment_id).try_collect::<Vec<_>>();
+        let update = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            set_state(&server, deployment_id, State::Stopped);
+        };
+        let (changes, ()) = futures::join!(watch, update);
+
+        let states: Vec<_> = changes.unwrap().iter().map(|c| (c.previous, c.state())).collect();
+        assert_eq!(states, [(None, State::Running), (Some(State::Running), State::Stopped)]);
+    }
+
+    #[tokio::test]
+    async fn watch_ends_on_initial_terminal_state() {
+        let (_server, client, deployment_id) = setup(State::Stopped).await;
+
+        let changes: Vec<_> = client.watch_deployment(deployment_id).try_collect().await.unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].state(), State::Stopped);
+    }
+}
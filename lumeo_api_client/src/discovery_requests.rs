@@ -4,7 +4,7 @@ use serde_json::Value as JsonValue;
 use uuid::Uuid;
 
 use super::{cameras::CameraData, Client};
-use crate::Result;
+use crate::{GatewayClient, Result};
 
 #[derive(Deserialize, Serialize)]
 pub struct DiscoveryRequest {
@@ -30,20 +30,32 @@ pub enum DiscoveryResult {
     Error(JsonValue),
 }
 
+impl GatewayClient<'_> {
+    pub async fn put_discovery_response(
+        &self,
+        request_id: Uuid,
+        data: &DiscoveryRequestData,
+    ) -> Result<()> {
+        let application_id = self.application_id();
+        let gateway_id = self.gateway_id();
+        self.client()
+            .put_without_response_deserialization(
+                &format!(
+                    "/v1/apps/{application_id}/gateways/{gateway_id}/discovery_request/{request_id}"
+                ),
+                Some(data),
+            )
+            .await
+    }
+}
+
 impl Client {
+    #[deprecated(note = "Gateway endpoint, use `GatewayClient::put_discovery_response`")]
     pub async fn put_discovery_response(
         &self,
         request_id: Uuid,
         data: &DiscoveryRequestData,
     ) -> Result<()> {
-        let application_id = self.application_id()?;
-        let gateway_id = self.gateway_id()?;
-        self.put_without_response_deserialization(
-            &format!(
-                "/v1/apps/{application_id}/gateways/{gateway_id}/discovery_request/{request_id}"
-            ),
-            Some(data),
-        )
-        .await
+        self.default_gateway()?.put_discovery_response(request_id, data).await
     }
 }
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, io, path::PathBuf, time::Duration};
 
 use reqwest::{Method, Response, StatusCode};
 use serde::{Deserialize, Serialize};
@@ -6,8 +6,16 @@ use strum::{AsRefStr, EnumString};
 use thiserror::Error;
 
 const RESOURCE_KEY: &str = "resource";
+const FIELDS_KEY: &str = "fields";
+const RETRY_AFTER_KEY: &str = "retry_after";
 
-use crate::Result;
+use uuid::Uuid;
+
+use crate::{
+    deployments::{Action, State},
+    pipeline::ConfigurationError,
+    retry, Result,
+};
 
 #[derive(AsRefStr, Error, Debug)]
 #[strum(serialize_all = "kebab-case")]
@@ -18,10 +26,33 @@ pub enum ApiError {
     InvalidCredentials,
     #[error("Resource not found (`resource-not-found`), resource: {0}")]
     ResourceNotFound(#[source] ResourceNotFound),
+    #[error("{message} (`validation-failed`)")]
+    ValidationFailed { message: String, fields: Vec<FieldError> },
+    #[error("{message} (`conflict`)")]
+    Conflict { message: String },
+    #[error("{message} (`forbidden`)")]
+    Forbidden { message: String },
+    /// `retry_after` is taken from the error context or the `Retry-After` header.
+    #[error("{message} (`rate-limited`)")]
+    RateLimited { message: String, retry_after: Option<Duration> },
+    #[error("{message} (`quota-exceeded`)")]
+    QuotaExceeded { message: String },
     #[doc(hidden)]
     #[strum(disabled)]
     #[error("{message} (`{code}`)")]
-    Other { code: String, message: String },
+    Other {
+        code: String,
+        message: String,
+        // Boxed to keep `Error` small, it's rarely there.
+        context: Option<Box<serde_json::Value>>,
+    },
+}
+
+/// Why the value of a single field of a request was rejected.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
 }
 
 #[derive(EnumString, Debug, Error)]
@@ -29,6 +60,24 @@ pub enum ResourceNotFound {
     #[error("Deployment")]
     #[strum(serialize = "deployment")]
     DeploymentNotFound,
+    #[error("Camera")]
+    #[strum(serialize = "camera")]
+    CameraNotFound,
+    #[error("Stream")]
+    #[strum(serialize = "stream")]
+    StreamNotFound,
+    #[error("File")]
+    #[strum(serialize = "file")]
+    FileNotFound,
+    #[error("Gateway")]
+    #[strum(serialize = "gateway")]
+    GatewayNotFound,
+    #[error("Model")]
+    #[strum(serialize = "model")]
+    ModelNotFound,
+    #[error("Application")]
+    #[strum(serialize = "application")]
+    ApplicationNotFound,
     #[doc(hidden)]
     #[strum(disabled)]
     #[error("Other({0})")]
@@ -53,11 +102,11 @@ impl Default for ResourceNotFound {
 }
 
 // Response from server
-#[derive(Debug, Deserialize, Serialize, Default)]
-struct ApiServerResponse {
-    code: String,
-    message: String,
-    context: Option<serde_json::Value>,
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub(crate) struct ApiServerResponse {
+    pub(crate) code: String,
+    pub(crate) message: String,
+    pub(crate) context: Option<serde_json::Value>,
 }
 
 impl<'de> Deserialize<'de> for ApiError {
@@ -68,20 +117,59 @@ impl<'de> Deserialize<'de> for ApiError {
         let ApiServerResponse { code, message, context } =
             ApiServerResponse::deserialize(deserializer)?;
 
-        Ok(if code == ApiError::GatewayDeleted.as_ref() {
+        let context_value = |key| context.as_ref()?.as_object()?.get(key);
+
+        let empty = String::new;
+        let is = |api_error: ApiError| code == api_error.as_ref();
+
+        Ok(if is(ApiError::GatewayDeleted) {
             ApiError::GatewayDeleted
-        } else if code == ApiError::InvalidCredentials.as_ref() {
+        } else if is(ApiError::InvalidCredentials) {
             ApiError::InvalidCredentials
-        } else if code == ApiError::ResourceNotFound(Default::default()).as_ref() {
-            context
-                .and_then(ResourceNotFound::from_context)
-                .map_or_else(|| ApiError::Other { code, message }, ApiError::ResourceNotFound)
+        } else if is(ApiError::ResourceNotFound(Default::default())) {
+            match context.clone().and_then(ResourceNotFound::from_context) {
+                Some(resource) => ApiError::ResourceNotFound(resource),
+                None => ApiError::Other { code, message, context: context.map(Box::new) },
+            }
+        } else if is(ApiError::ValidationFailed { message: empty(), fields: vec![] }) {
+            let fields = context_value(FIELDS_KEY)
+                .and_then(|fields| serde_json::from_value(fields.clone()).ok())
+                .unwrap_or_default();
+            ApiError::ValidationFailed { message, fields }
+        } else if is(ApiError::Conflict { message: empty() }) {
+            ApiError::Conflict { message }
+        } else if is(ApiError::Forbidden { message: empty() }) {
+            ApiError::Forbidden { message }
+        } else if is(ApiError::RateLimited { message: empty(), retry_after: None }) {
+            let retry_after =
+                context_value(RETRY_AFTER_KEY).and_then(|v| v.as_u64()).map(Duration::from_secs);
+            ApiError::RateLimited { message, retry_after }
+        } else if is(ApiError::QuotaExceeded { message: empty() }) {
+            ApiError::QuotaExceeded { message }
         } else {
-            ApiError::Other { code, message }
+            ApiError::Other { code, message, context: context.map(Box::new) }
         })
     }
 }
 
+impl ApiError {
+    /// How long the server asked to wait before sending the request again.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ApiError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// The error code sent by the server, e.g. `resource-not-found`.
+    pub fn code(&self) -> &str {
+        match self {
+            ApiError::Other { code, .. } => code,
+            api_error => api_error.as_ref(),
+        }
+    }
+}
+
 /// Checks if response status isn't a success,
 /// and then tries to extract json error description from it
 ///
@@ -100,6 +188,7 @@ pub(crate) async fn verify_response(
 
     if !response.status().is_success() {
         let details = ErrorDetails::new(method, path, Some(response.status()));
+        let header_retry_after = retry::retry_after(&response);
         let body = match response.bytes().await {
             Ok(b) => b,
             Err(e) => return Err(Error::Reqwest(e, details)),
@@ -110,6 +199,10 @@ pub(crate) async fn verify_response(
         }
 
         match serde_json::from_slice(&body) {
+            Ok(ApiError::RateLimited { message, retry_after: None }) => {
+                let api_error = ApiError::RateLimited { message, retry_after: header_retry_after };
+                return Err(Error::Api(api_error, details));
+            }
             Ok(api_error) => return Err(Error::Api(api_error, details)),
             Err(e) => return Err(Error::Deserialization(e, details)),
         }
@@ -126,7 +219,7 @@ pub struct ErrorDetails {
 }
 
 impl ErrorDetails {
-    fn new(method: Method, path: &str, status: Option<StatusCode>) -> Self {
+    pub(crate) fn new(method: Method, path: &str, status: Option<StatusCode>) -> Self {
         Self { method, path: path.to_owned(), status }
     }
 }
@@ -164,6 +257,94 @@ pub enum Error {
     ApplicationIdMissing,
     #[error("Gateway id is missing")]
     GatewayIdMissing,
+    #[error("API token environment variable `{0}` is not set")]
+    TokenEnvVarMissing(String),
+    #[error("Deployment {deployment_id} didn't reach state `{target}` within {timeout:?}")]
+    DeploymentStateTimeout {
+        deployment_id: Uuid,
+        target: State,
+        /// Last state observed, if any
+        last: Option<State>,
+        timeout: Duration,
+    },
+    #[error("Deployment {deployment_id} went to state `{state}` instead of `{target}`")]
+    UnexpectedDeploymentState { deployment_id: Uuid, target: State, state: State },
+    #[error("Deployment can't go from state `{from}` to `{to}`")]
+    IllegalStateTransition { from: State, to: State },
+    #[error("Can't {action} deployment {deployment_id} in state `{state}`")]
+    IllegalDeploymentAction { deployment_id: Uuid, action: Action, state: State },
+    #[error("Invalid deployment configuration: {0}")]
+    InvalidConfiguration(#[source] ConfigurationError),
+    #[error("File {file_id} has no {kind} to download")]
+    FileContentMissing { file_id: Uuid, kind: &'static str },
+    #[error("Upload of file {file_id} acknowledged offset {acknowledged} instead of {expected}")]
+    UploadOffsetMismatch { file_id: Uuid, expected: u64, acknowledged: u64 },
+    #[error("More than {limit} records share a creation time, they can't be paginated")]
+    PaginationStalled { limit: i16 },
+    #[error("Exported records are written newest first, `sort_by` and `sort_order` must be unset")]
+    SortedExport,
+    #[error("{}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl Error {
+    /// The HTTP status the server answered with, if the request got a response.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.details()?.status
+    }
+
+    /// Whether the requested resource doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::Api(ApiError::ResourceNotFound(_), _))
+            || self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// Whether the error is transient, i.e. the same request may succeed later.
+    ///
+    /// This is the case if the request could not be sent, or the server is rate limiting or
+    /// temporarily unavailable. Whether it is safe to send a request again also depends on its
+    /// method, see [`RetryPolicy`](crate::RetryPolicy).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Reqwest(e, _) if e.is_connect() || e.is_timeout() => true,
+            Error::Api(ApiError::RateLimited { .. }, _) => true,
+            _ => self.status().map_or(false, is_retryable_status),
+        }
+    }
+
+    fn details(&self) -> Option<&ErrorDetails> {
+        match self {
+            Error::Url(_, details)
+            | Error::Query(_, details)
+            | Error::Reqwest(_, details)
+            | Error::Api(_, details)
+            | Error::ApiEmptyResponse(details)
+            | Error::Deserialization(_, details) => Some(details),
+            Error::ApplicationIdMissing
+            | Error::GatewayIdMissing
+            | Error::TokenEnvVarMissing(_)
+            | Error::DeploymentStateTimeout { .. }
+            | Error::UnexpectedDeploymentState { .. }
+            | Error::IllegalStateTransition { .. }
+            | Error::IllegalDeploymentAction { .. }
+            | Error::InvalidConfiguration(_)
+            | Error::FileContentMissing { .. }
+            | Error::UploadOffsetMismatch { .. }
+            | Error::PaginationStalled { .. }
+            | Error::SortedExport
+            | Error::Io { .. } => None,
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
 }
 
 pub(crate) trait ResultExt<T> {
@@ -223,4 +404,143 @@ mod tests {
         let error: ApiError = serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
         assert!(matches!(error, ApiError::InvalidCredentials));
     }
+
+    #[test]
+    fn resource_not_found_for_every_resource() {
+        let error = |resource| {
+            let resp = ApiServerResponse {
+                code: "resource-not-found".to_owned(),
+                context: serde_json::json!({ "resource": resource }).into(),
+                ..Default::default()
+            };
+            serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap()
+        };
+
+        assert!(matches!(
+            error("camera"),
+            ApiError::ResourceNotFound(ResourceNotFound::CameraNotFound)
+        ));
+        assert!(matches!(
+            error("stream"),
+            ApiError::ResourceNotFound(ResourceNotFound::StreamNotFound)
+        ));
+        assert!(matches!(
+            error("file"),
+            ApiError::ResourceNotFound(ResourceNotFound::FileNotFound)
+        ));
+        assert!(matches!(
+            error("gateway"),
+            ApiError::ResourceNotFound(ResourceNotFound::GatewayNotFound)
+        ));
+        assert!(matches!(
+            error("model"),
+            ApiError::ResourceNotFound(ResourceNotFound::ModelNotFound)
+        ));
+        assert!(matches!(
+            error("application"),
+            ApiError::ResourceNotFound(ResourceNotFound::ApplicationNotFound)
+        ));
+        assert!(matches!(
+            error("pipeline"),
+            ApiError::ResourceNotFound(ResourceNotFound::Other(resource)) if resource == "pipeline"
+        ));
+    }
+
+    #[test]
+    fn validation_failed() {
+        let resp = ApiServerResponse {
+            code: "validation-failed".to_owned(),
+            message: "Invalid deployment".to_owned(),
+            context: serde_json::json!({
+                "fields": [{ "field": "name", "message": "must not be empty" }],
+            })
+            .into(),
+        };
+
+        let error: ApiError = serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        match error {
+            ApiError::ValidationFailed { message, fields } => {
+                assert_eq!(message, "Invalid deployment");
+                assert_eq!(
+                    fields,
+                    [FieldError {
+                        field: "name".to_owned(),
+                        message: "must not be empty".to_owned()
+                    }]
+                );
+            }
+            error => panic!("Unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn rate_limited() {
+        let resp = ApiServerResponse {
+            code: "rate-limited".to_owned(),
+            context: serde_json::json!({ "retry_after": 30 }).into(),
+            ..Default::default()
+        };
+
+        let error: ApiError = serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        assert!(matches!(
+            error,
+            ApiError::RateLimited { retry_after: Some(retry_after), .. }
+                if retry_after == Duration::from_secs(30)
+        ));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(30)));
+        assert_eq!(error.code(), "rate-limited");
+    }
+
+    #[test]
+    fn other_keeps_context() {
+        let resp = ApiServerResponse {
+            code: "payment-required".to_owned(),
+            context: serde_json::json!({ "plan": "free" }).into(),
+            ..Default::default()
+        };
+
+        let error: ApiError = serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        assert_eq!(error.code(), "payment-required");
+        assert!(matches!(error, ApiError::Other { context: Some(_), .. }));
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let api_error = |api_error, status| {
+            Error::Api(api_error, ErrorDetails::new(Method::GET, "/v1/orgs", Some(status)))
+        };
+
+        let not_found = api_error(
+            ApiError::ResourceNotFound(ResourceNotFound::CameraNotFound),
+            StatusCode::NOT_FOUND,
+        );
+        assert!(not_found.is_not_found());
+        assert!(!not_found.is_retryable());
+        assert_eq!(not_found.status(), Some(StatusCode::NOT_FOUND));
+
+        let rate_limited = api_error(
+            ApiError::RateLimited { message: String::new(), retry_after: None },
+            StatusCode::TOO_MANY_REQUESTS,
+        );
+        assert!(rate_limited.is_retryable());
+        assert!(!rate_limited.is_not_found());
+
+        let conflict =
+            api_error(ApiError::Conflict { message: String::new() }, StatusCode::CONFLICT);
+        assert!(!conflict.is_retryable());
+        assert_eq!(conflict.status(), Some(StatusCode::CONFLICT));
+
+        assert_eq!(Error::ApplicationIdMissing.status(), None);
+        assert!(!Error::ApplicationIdMissing.is_retryable());
+    }
+
+    #[test]
+    fn retries_only_transient_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::GATEWAY_TIMEOUT));
+        assert!(!is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+    }
 }
@@ -1,10 +1,12 @@
+use std::fmt;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, EnumString};
 use uuid::Uuid;
 
 use super::Client;
-use crate::Result;
+use crate::{AppClient, Result};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, AsRefStr, EnumString)]
 #[serde(rename_all = "snake_case")]
@@ -26,7 +28,7 @@ pub struct EventData {
     pub object_id: Option<Uuid>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Event {
     pub id: Uuid,
     pub created_at: DateTime<Utc>,
@@ -40,8 +42,16 @@ pub struct Event {
     pub object_id: Option<Uuid>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+impl Event {
+    /// The error reported with [`AppClient::create_error_event`], if this is an error event.
+    pub fn error_data(&self) -> Option<ErrorData> {
+        serde_json::from_str(self.payload.as_deref()?).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, AsRefStr)]
 #[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
 pub enum GstErrorDomain {
     Core = 1,
     Library = 2,
@@ -49,27 +59,167 @@ pub enum GstErrorDomain {
     Stream = 4,
 }
 
-#[derive(Serialize)]
+impl GstErrorDomain {
+    /// Name of the GStreamer error `code` of this domain, e.g. `GST_RESOURCE_ERROR_NOT_FOUND`.
+    pub fn error_name(self, code: i32) -> Option<&'static str> {
+        let names: &[&str] = match self {
+            GstErrorDomain::Core => &[
+                "GST_CORE_ERROR_FAILED",
+                "GST_CORE_ERROR_TOO_LAZY",
+                "GST_CORE_ERROR_NOT_IMPLEMENTED",
+                "GST_CORE_ERROR_STATE_CHANGE",
+                "GST_CORE_ERROR_PAD",
+                "GST_CORE_ERROR_THREAD",
+                "GST_CORE_ERROR_NEGOTIATION",
+                "GST_CORE_ERROR_EVENT",
+                "GST_CORE_ERROR_SEEK",
+                "GST_CORE_ERROR_CAPS",
+                "GST_CORE_ERROR_TAG",
+                "GST_CORE_ERROR_MISSING_PLUGIN",
+                "GST_CORE_ERROR_CLOCK",
+                "GST_CORE_ERROR_DISABLED",
+            ],
+            GstErrorDomain::Library => &[
+                "GST_LIBRARY_ERROR_FAILED",
+                "GST_LIBRARY_ERROR_INIT",
+                "GST_LIBRARY_ERROR_SHUTDOWN",
+                "GST_LIBRARY_ERROR_SETTINGS",
+                "GST_LIBRARY_ERROR_ENCODE",
+            ],
+            GstErrorDomain::Resource => &[
+                "GST_RESOURCE_ERROR_FAILED",
+                "GST_RESOURCE_ERROR_TOO_LAZY",
+                "GST_RESOURCE_ERROR_NOT_FOUND",
+                "GST_RESOURCE_ERROR_BUSY",
+                "GST_RESOURCE_ERROR_OPEN_READ",
+                "GST_RESOURCE_ERROR_OPEN_WRITE",
+                "GST_RESOURCE_ERROR_OPEN_READ_WRITE",
+                "GST_RESOURCE_ERROR_CLOSE",
+                "GST_RESOURCE_ERROR_READ",
+                "GST_RESOURCE_ERROR_WRITE",
+                "GST_RESOURCE_ERROR_SEEK",
+                "GST_RESOURCE_ERROR_SYNC",
+                "GST_RESOURCE_ERROR_SETTINGS",
+                "GST_RESOURCE_ERROR_NO_SPACE_LEFT",
+                "GST_RESOURCE_ERROR_NOT_AUTHORIZED",
+            ],
+            GstErrorDomain::Stream => &[
+                "GST_STREAM_ERROR_FAILED",
+                "GST_STREAM_ERROR_TOO_LAZY",
+                "GST_STREAM_ERROR_NOT_IMPLEMENTED",
+                "GST_STREAM_ERROR_TYPE_NOT_FOUND",
+                "GST_STREAM_ERROR_WRONG_TYPE",
+                "GST_STREAM_ERROR_CODEC_NOT_FOUND",
+                "GST_STREAM_ERROR_DECODE",
+                "GST_STREAM_ERROR_ENCODE",
+                "GST_STREAM_ERROR_DEMUX",
+                "GST_STREAM_ERROR_MUX",
+                "GST_STREAM_ERROR_FORMAT",
+                "GST_STREAM_ERROR_DECRYPT",
+                "GST_STREAM_ERROR_DECRYPT_NOKEY",
+            ],
+        };
+
+        // Codes start at 1 in every domain.
+        let index = usize::try_from(code).ok()?.checked_sub(1)?;
+        names.get(index).copied()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ErrorData {
     Deployment { deployment_id: Uuid, error: DeploymentError },
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum DeploymentError {
     GstError { domain: GstErrorDomain, code: i32 },
 }
 
-impl Client {
+impl fmt::Display for DeploymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeploymentError::GstError { domain, code } => match domain.error_name(*code) {
+                Some(name) => write!(f, "GStreamer error {name}"),
+                None => write!(f, "GStreamer {} error {code}", domain.as_ref()),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ListParams {
+    /// Maximum number of events to return
+    pub limit: i16,
+    /// Filter: Lower bound for creation time (inclusive)
+    pub created_ts_since: Option<DateTime<Utc>>,
+    /// Filter: Upper bound for creation time (exclusive)
+    pub created_ts_until: Option<DateTime<Utc>>,
+    /// Filter: Object ID(s), e.g. of deployments
+    pub object_ids: Vec<Uuid>,
+    /// Filter: Severities
+    pub severities: Vec<Severity>,
+    /// Filter: Event type(s)
+    pub event_types: Vec<String>,
+}
+
+impl AppClient<'_> {
+    pub async fn list_events(&self, params: &ListParams) -> Result<Vec<Event>> {
+        let application_id = self.application_id();
+        self.client().get(&format!("/v1/apps/{application_id}/events"), Some(params)).await
+    }
+
     pub async fn create_event(&self, event: &EventData) -> Result<Event> {
-        let application_id = self.application_id()?;
-        self.post(&format!("/v1/apps/{application_id}/events"), event).await
+        let application_id = self.application_id();
+        self.client().post(&format!("/v1/apps/{application_id}/events"), event).await
     }
 
     pub async fn create_error_event(&self, error_data: &ErrorData) -> Result<Event> {
-        let application_id = self.application_id()?;
-        self.post(&format!("/v1/internal/apps/{application_id}/events/error_events"), error_data)
+        let application_id = self.application_id();
+        self.client()
+            .post(&format!("/v1/internal/apps/{application_id}/events/error_events"), error_data)
             .await
     }
 }
+
+impl Client {
+    pub async fn list_events(&self, params: &ListParams) -> Result<Vec<Event>> {
+        self.default_app()?.list_events(params).await
+    }
+
+    pub async fn create_event(&self, event: &EventData) -> Result<Event> {
+        self.default_app()?.create_event(event).await
+    }
+
+    pub async fn create_error_event(&self, error_data: &ErrorData) -> Result<Event> {
+        self.default_app()?.create_error_event(error_data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gst_error_names() {
+        assert_eq!(GstErrorDomain::Core.error_name(1), Some("GST_CORE_ERROR_FAILED"));
+        assert_eq!(GstErrorDomain::Core.error_name(14), Some("GST_CORE_ERROR_DISABLED"));
+        assert_eq!(GstErrorDomain::Library.error_name(5), Some("GST_LIBRARY_ERROR_ENCODE"));
+        assert_eq!(GstErrorDomain::Resource.error_name(3), Some("GST_RESOURCE_ERROR_NOT_FOUND"));
+        assert_eq!(GstErrorDomain::Stream.error_name(13), Some("GST_STREAM_ERROR_DECRYPT_NOKEY"));
+        assert_eq!(GstErrorDomain::Stream.error_name(14), None);
+        assert_eq!(GstErrorDomain::Core.error_name(0), None);
+        assert_eq!(GstErrorDomain::Core.error_name(-1), None);
+    }
+
+    #[test]
+    fn deployment_error_display() {
+        let error = DeploymentError::GstError { domain: GstErrorDomain::Resource, code: 4 };
+        assert_eq!(error.to_string(), "GStreamer error GST_RESOURCE_ERROR_BUSY");
+
+        let error = DeploymentError::GstError { domain: GstErrorDomain::Library, code: 42 };
+        assert_eq!(error.to_string(), "GStreamer library error 42");
+    }
+}
@@ -1,4 +1,7 @@
+use std::path::{Path, PathBuf};
+
 use chrono::{DateTime, Utc};
+use futures::{future, stream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use strum::Display;
 use thiserror::Error;
@@ -6,7 +9,20 @@ use url::Url;
 use uuid::Uuid;
 
 use super::Client;
-use crate::Result;
+use crate::{
+    lumeo_url::{LumeoUrl, LumeoUrlError},
+    pagination::{paginate, Paginated},
+    AppClient, Result,
+};
+
+mod chunked;
+mod export;
+mod sync;
+mod transfer;
+
+pub use chunked::{ChunkedUpload, DEFAULT_CHUNK_SIZE};
+pub use export::ExportFormat;
+pub use sync::{LocalFileSync, SyncReport};
 
 #[derive(Clone, Copy, Debug, Display, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -32,7 +48,7 @@ pub struct FileData {
     pub stream_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct File {
     pub id: Uuid,
     pub name: String,
@@ -52,47 +68,71 @@ pub struct File {
     pub metadata_url: Option<Url>,
 }
 
+impl Paginated for File {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
 impl File {
     pub fn stream_url(&self) -> Url {
         create_lumeo_file_url(self.id)
     }
+
+    pub fn lumeo_url(&self) -> LumeoUrl {
+        LumeoUrl::File(self.id)
+    }
 }
 
+/// Returns the legacy `lumeo://<file_id>` URL of the file, see [`LumeoUrl`] for the current one.
 pub fn create_lumeo_file_url(file_id: Uuid) -> Url {
     format!("lumeo://{file_id}").parse().unwrap_or_else(|error| {
         unreachable!("Failed to parse generated Lumeo URL with error: {error}")
     })
 }
 
+/// Returns the file ID of a `lumeo://files/<file_id>` or legacy `lumeo://<file_id>` URL, see
+/// [`LumeoUrl`].
 pub fn parse_file_id_from_lumeo_url(url: &Url) -> std::result::Result<Uuid, FileUrlError> {
-    if url.scheme() != "lumeo" {
-        return Err(FileUrlError::InvalidScheme(url.scheme().into()));
-    }
-
-    let file_id = url.host_str().ok_or(FileUrlError::MissingHost)?;
-
-    if url.as_str().len() != ("lumeo://".len() + file_id.len()) {
-        return Err(FileUrlError::InvalidLength);
-    }
+    let lumeo_url = LumeoUrl::try_from(url).map_err(|err| match err {
+        LumeoUrlError::InvalidScheme(scheme) => FileUrlError::InvalidScheme(scheme),
+        LumeoUrlError::InvalidUuid(id) => FileUrlError::InvalidUuid(id),
+        LumeoUrlError::InvalidUrl(_) | LumeoUrlError::Unsupported(_) => {
+            FileUrlError::Unsupported(url.as_str().into())
+        }
+    })?;
+
+    lumeo_url.file_id().ok_or_else(|| FileUrlError::Unsupported(url.as_str().into()))
+}
 
-    Uuid::parse_str(file_id).map_err(|_| FileUrlError::InvalidUuid(file_id.into()))
+/// Path of the temporary file written before it replaces `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".part");
+    path.with_file_name(name)
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum FileUrlError {
     #[error("Invalid scheme, expected 'lumeo', got '{0}'")]
     InvalidScheme(String),
-    #[error("Missing host")]
-    MissingHost,
-    #[error("Invalid length")]
-    InvalidLength,
+    #[error("Not a Lumeo file URL: '{0}'")]
+    Unsupported(String),
     #[error("Invalid UUID: '{0}'")]
     InvalidUuid(String),
 }
 
-#[derive(Debug, Default, Serialize)]
+/// Parameters of [`AppClient::list_files`].
+///
+/// The size, duration, name and local path filters and the sort parameters must be supported
+/// by the API server, older versions don't apply them.
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct ListParams {
-    /// Maximum number of files to return
+    /// Maximum number of files to return (page size for [`AppClient::list_files_stream`])
     pub limit: i16,
     /// Filter: Lower bound for creation time (inclusive)
     pub created_ts_since: Option<DateTime<Utc>>,
@@ -110,29 +150,154 @@ pub struct ListParams {
     pub gateway_ids: Vec<Uuid>,
     /// Filter: Pipeline ID(s)
     pub pipeline_ids: Vec<Uuid>,
+    /// Filter: Cloud status(es)
+    pub cloud_statuses: Vec<FileCloudStatus>,
+    /// Filter: Lower bound for the size in bytes (inclusive)
+    pub min_size: Option<i64>,
+    /// Filter: Upper bound for the size in bytes (inclusive)
+    pub max_size: Option<i64>,
+    /// Filter: Lower bound for the duration (inclusive)
+    pub min_duration: Option<i32>,
+    /// Filter: Upper bound for the duration (inclusive)
+    pub max_duration: Option<i32>,
+    /// Filter: Name pattern, where `*` matches any characters (e.g. `*.mp4`)
+    pub name_pattern: Option<String>,
+    /// Filter: Whether the file has a local copy, i.e. a `local_path`
+    pub has_local_path: Option<bool>,
+    /// Sort key, newest first by default. Ignored by [`AppClient::list_files_stream`], which
+    /// relies on the default order, and rejected by [`AppClient::export_files_to`].
+    pub sort_by: Option<FileSortKey>,
+    /// Ascending by default, if `sort_by` is set
+    pub sort_order: Option<SortOrder>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSortKey {
+    CreatedAt,
+    Name,
+    Size,
+    Duration,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
 }
 
-pub type DeleteParams = ListParams;
+/// Parameters of [`AppClient::delete_files`].
+///
+/// Unlike [`ListParams`], it only has the filters every API server supports, so a filter is
+/// never ignored and a delete never affects more files than intended.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DeleteParams {
+    /// Maximum number of files to delete
+    pub limit: i16,
+    /// Filter: Lower bound for creation time (inclusive)
+    pub created_ts_since: Option<DateTime<Utc>>,
+    /// Filter: Upper bound for creation time (exclusive)
+    pub created_ts_until: Option<DateTime<Utc>>,
+    /// Filter: Node ID(s)
+    pub node_ids: Vec<String>,
+    /// Filter: Deployment ID(s)
+    pub deployment_ids: Vec<Uuid>,
+    /// Filter: Camera ID(s)
+    pub camera_ids: Vec<Uuid>,
+    /// Filter: Stream ID(s)
+    pub stream_ids: Vec<Uuid>,
+    /// Filter: Gateway ID(s)
+    pub gateway_ids: Vec<Uuid>,
+    /// Filter: Pipeline ID(s)
+    pub pipeline_ids: Vec<Uuid>,
+}
+
+impl<'a> AppClient<'a> {
+    pub async fn list_files(&self, params: Option<&ListParams>) -> Result<Vec<File>> {
+        let application_id = self.application_id();
+        self.client().get(&format!("/v1/apps/{application_id}/files"), params).await
+    }
+
+    /// Returns all files matching `params`, newest first, fetching them page by page.
+    pub fn list_files_stream(self, params: ListParams) -> impl Stream<Item = Result<File>> + 'a {
+        paginate(params.created_ts_until, params.limit, move |created_ts_until, limit| {
+            let params = ListParams {
+                created_ts_until,
+                limit,
+                sort_by: None,
+                sort_order: None,
+                ..params.clone()
+            };
+            async move { self.list_files(Some(&params)).await }
+        })
+    }
+
+    pub async fn create_file(&self, file_data: &FileData) -> Result<File> {
+        let application_id = self.application_id();
+        self.client().post(&format!("/v1/apps/{application_id}/files"), file_data).await
+    }
+
+    pub async fn read_file(&self, file_id: Uuid) -> Result<File> {
+        let application_id = self.application_id();
+        self.client().get(&format!("/v1/apps/{application_id}/files/{file_id}"), None::<&()>).await
+    }
+
+    pub async fn update_file(&self, file_id: Uuid, file_data: &FileData) -> Result<File> {
+        let application_id = self.application_id();
+        self.client().put(&format!("/v1/apps/{application_id}/files/{file_id}"), file_data).await
+    }
+
+    pub async fn update_cloud_status(
+        &self,
+        file_id: Uuid,
+        cloud_status: &FileCloudStatus,
+    ) -> Result<()> {
+        let application_id = self.application_id();
+        self.client()
+            .put_text(
+                &format!("/v1/apps/{application_id}/files/{file_id}/cloud_status"),
+                cloud_status,
+            )
+            .await
+    }
+
+    pub async fn delete_file(&self, file_id: Uuid) -> Result<()> {
+        let application_id = self.application_id();
+        self.client()
+            .delete(&format!("/v1/apps/{application_id}/files/{file_id}"), None::<&()>)
+            .await
+    }
+
+    pub async fn delete_files(&self, params: &DeleteParams) -> Result<()> {
+        let application_id = self.application_id();
+        self.client().delete(&format!("/v1/apps/{application_id}/files"), Some(params)).await
+    }
+}
 
 impl Client {
     pub async fn list_files(&self, params: Option<&ListParams>) -> Result<Vec<File>> {
-        let application_id = self.application_id()?;
-        self.get(&format!("/v1/apps/{application_id}/files"), params).await
+        self.default_app()?.list_files(params).await
+    }
+
+    /// Returns all files matching `params`, newest first, fetching them page by page.
+    pub fn list_files_stream(&self, params: ListParams) -> impl Stream<Item = Result<File>> + '_ {
+        match self.default_app() {
+            Ok(app) => app.list_files_stream(params).left_stream(),
+            Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+        }
     }
 
     pub async fn create_file(&self, file_data: &FileData) -> Result<File> {
-        let application_id = self.application_id()?;
-        self.post(&format!("/v1/apps/{application_id}/files"), file_data).await
+        self.default_app()?.create_file(file_data).await
     }
 
     pub async fn read_file(&self, file_id: Uuid) -> Result<File> {
-        let application_id = self.application_id()?;
-        self.get(&format!("/v1/apps/{application_id}/files/{file_id}"), None::<&()>).await
+        self.default_app()?.read_file(file_id).await
     }
 
     pub async fn update_file(&self, file_id: Uuid, file_data: &FileData) -> Result<File> {
-        let application_id = self.application_id()?;
-        self.put(&format!("/v1/apps/{application_id}/files/{file_id}"), file_data).await
+        self.default_app()?.update_file(file_id, file_data).await
     }
 
     pub async fn update_cloud_status(
@@ -140,22 +305,15 @@ impl Client {
         file_id: Uuid,
         cloud_status: &FileCloudStatus,
     ) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.put_text(
-            &format!("/v1/apps/{application_id}/files/{file_id}/cloud_status"),
-            cloud_status,
-        )
-        .await
+        self.default_app()?.update_cloud_status(file_id, cloud_status).await
     }
 
     pub async fn delete_file(&self, file_id: Uuid) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.delete(&format!("/v1/apps/{application_id}/files/{file_id}"), None::<&()>).await
+        self.default_app()?.delete_file(file_id).await
     }
 
     pub async fn delete_files(&self, params: &DeleteParams) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.delete(&format!("/v1/apps/{application_id}/files"), Some(params)).await
+        self.default_app()?.delete_files(params).await
     }
 }
 
@@ -184,19 +342,37 @@ mod test {
         assert_eq!(expected_file_id, file_id);
     }
 
+    #[test]
+    fn should_parse_current_lumeo_file_url() {
+        let url = Url::parse("lumeo://files/f65c8128-e25a-11ec-b486-efa3b8212d7f")
+            .expect("Failed to parse URL");
+
+        let file_id = parse_file_id_from_lumeo_url(&url).expect("Failed to parse lumeo file URL");
+
+        assert_eq!(file_id.to_string(), "f65c8128-e25a-11ec-b486-efa3b8212d7f");
+        let url = Url::parse("lumeo://streams/f65c8128-e25a-11ec-b486-efa3b8212d7f").unwrap();
+        assert!(parse_file_id_from_lumeo_url(&url).is_err());
+    }
+
     #[test]
     fn should_not_parse_lumeo_file_url_with_query() {
         let url = Url::parse("lumeo://f65c8128-e25a-11ec-b486-efa3b8212d7f?key=value")
             .expect("Failed to parse URL");
 
-        assert_eq!(Err(FileUrlError::InvalidLength), parse_file_id_from_lumeo_url(&url));
+        assert_eq!(
+            Err(FileUrlError::Unsupported(url.as_str().into())),
+            parse_file_id_from_lumeo_url(&url)
+        );
     }
 
     #[test]
     fn should_not_parse_lumeo_file_url_without_host() {
         let url = Url::parse("lumeo://").expect("Failed to parse URL");
 
-        assert_eq!(Err(FileUrlError::MissingHost), parse_file_id_from_lumeo_url(&url));
+        assert_eq!(
+            Err(FileUrlError::Unsupported("lumeo://".into())),
+            parse_file_id_from_lumeo_url(&url)
+        );
     }
 
     #[test]
@@ -0,0 +1,313 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::AsyncReadExt};
+use uuid::Uuid;
+
+use super::FileCloudStatus;
+use crate::{
+    error::{ApiError, Error, ResultExt},
+    AppClient, Client, Result,
+};
+
+/// Size of the chunks of resumable uploads by default.
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Settings of resumable uploads, see [`AppClient::upload_file_chunked`].
+#[derive(Clone, Debug)]
+pub struct ChunkedUpload {
+    progress_dir: PathBuf,
+    chunk_size: usize,
+    chunk_timeout: Option<Duration>,
+}
+
+impl ChunkedUpload {
+    /// Uploads persisting their progress in `progress_dir`, which must exist.
+    pub fn new(progress_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            progress_dir: progress_dir.into(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_timeout: None,
+        }
+    }
+
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Timeout of the upload of a single chunk, the client's transfer timeout by default (see
+    /// [`ClientBuilder::transfer_timeout`](crate::ClientBuilder::transfer_timeout)).
+    pub fn chunk_timeout(mut self, chunk_timeout: Duration) -> Self {
+        self.chunk_timeout = Some(chunk_timeout);
+        self
+    }
+
+    fn progress_path(&self, file_id: Uuid) -> PathBuf {
+        self.progress_dir.join(format!("{file_id}.upload.json"))
+    }
+}
+
+/// Progress of an upload, persisted after each acknowledged chunk.
+#[derive(Deserialize, Serialize)]
+struct Progress {
+    /// Size of the uploaded file, the progress is discarded if it changes
+    size: u64,
+    /// Number of bytes acknowledged by the server
+    offset: u64,
+}
+
+#[derive(Deserialize)]
+struct ChunkAck {
+    offset: u64,
+}
+
+#[derive(Serialize)]
+struct Completion {
+    size: u64,
+    sha256: String,
+}
+
+impl AppClient<'_> {
+    /// Uploads the file at `path` as content of the file in chunks, resuming a previous upload.
+    ///
+    /// The offset acknowledged by the server is persisted after each chunk, so that an upload
+    /// interrupted by an error or a restart continues from there when called again. Once all
+    /// chunks are uploaded, the server verifies the size and SHA-256 checksum of the content
+    /// before the file is marked `Uploaded` and the progress is deleted. If the checksum doesn't
+    /// match, the progress is deleted as well and the next attempt starts over.
+    pub async fn upload_file_chunked(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+        upload: &ChunkedUpload,
+    ) -> Result<()> {
+        let client = self.client();
+        let path = path.as_ref();
+        let progress_path = upload.progress_path(file_id);
+
+        let mut source = fs::File::open(path).await.map_err(io_error(client, path))?;
+        let size = source.metadata().await.map_err(io_error(client, path))?.len();
+        let mut offset = match read_progress(&progress_path).await {
+            Some(progress) if progress.size == size && progress.offset <= size => progress.offset,
+            _ => {
+                self.update_cloud_status(file_id, &FileCloudStatus::Uploading).await?;
+                0
+            }
+        };
+
+        // The checksum covers the whole content, including chunks uploaded before resuming.
+        let mut sha256 = Sha256::new();
+        let mut buffer = vec![0; upload.chunk_size];
+        let mut hashed = 0;
+        while hashed < offset {
+            let len = buffer.len().min((offset - hashed) as usize);
+            source.read_exact(&mut buffer[..len]).await.map_err(io_error(client, path))?;
+            sha256.update(&buffer[..len]);
+            hashed += len as u64;
+        }
+
+        let application_id = self.application_id();
+        let chunks_path = format!("/v1/apps/{application_id}/files/{file_id}/data/chunks");
+        let chunk_timeout = upload.chunk_timeout.unwrap_or(client.transfer_timeout);
+        while offset < size {
+            let len = read_chunk(&mut source, &mut buffer).await.map_err(io_error(client, path))?;
+            if len == 0 {
+                break;
+            }
+
+            let query = format!("offset={offset}");
+            let chunk = Bytes::copy_from_slice(&buffer[..len]);
+            let ack: ChunkAck = client
+                .put_body(&chunks_path, Some(&query), chunk, chunk_timeout)
+                .await?
+                .json()
+                .await
+                .http_context(Method::PUT, &chunks_path)
+                .map_err(|err| client.through_cb(err))?;
+
+            sha256.update(&buffer[..len]);
+            offset += len as u64;
+            if ack.offset != offset {
+                return Err(client.through_cb(Error::UploadOffsetMismatch {
+                    file_id,
+                    expected: offset,
+                    acknowledged: ack.offset,
+                }));
+            }
+            write_progress(&progress_path, &Progress { size, offset })
+                .await
+                .map_err(io_error(client, &progress_path))?;
+        }
+
+        let sha256 = sha256.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+        let complete_path = format!("/v1/apps/{application_id}/files/{file_id}/data/complete");
+        let result = client
+            .post_without_response_deserialization(
+                &complete_path,
+                Some(&Completion { size, sha256 }),
+            )
+            .await;
+        if let Err(err) = result {
+            if matches!(err, Error::Api(ApiError::ValidationFailed { .. }, _)) {
+                remove_progress(&progress_path).await.map_err(io_error(client, &progress_path))?;
+            }
+            return Err(err);
+        }
+
+        self.update_cloud_status(file_id, &FileCloudStatus::Uploaded).await?;
+        remove_progress(&progress_path).await.map_err(io_error(client, &progress_path))
+    }
+}
+
+impl Client {
+    pub async fn upload_file_chunked(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+        upload: &ChunkedUpload,
+    ) -> Result<()> {
+        self.default_app()?.upload_file_chunked(file_id, path, upload).await
+    }
+}
+
+fn io_error<'a>(client: &'a Client, path: &'a Path) -> impl Fn(io::Error) -> Error + 'a {
+    move |source| client.through_cb(Error::Io { path: path.into(), source })
+}
+
+/// Fills `buffer` unless the end of the file is reached first, and returns the number of bytes
+/// read.
+async fn read_chunk(source: &mut fs::File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match source.read(&mut buffer[len..]).await? {
+            0 => break,
+            read => len += read,
+        }
+    }
+    Ok(len)
+}
+
+/// Reads persisted progress, ignoring missing or corrupt files.
+async fn read_progress(path: &Path) -> Option<Progress> {
+    serde_json::from_slice(&fs::read(path).await.ok()?).ok()
+}
+
+async fn write_progress(path: &Path, progress: &Progress) -> io::Result<()> {
+    // Replace the file atomically so that a crash can't leave it truncated.
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(progress)?).await?;
+    fs::rename(tmp_path, path).await
+}
+
+async fn remove_progress(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::env;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{ErrorInjection, MockServer, Resource};
+
+    const CONTENT: &[u8] = b"0123456789";
+
+    async fn setup() -> (MockServer, Client, Uuid, PathBuf, ChunkedUpload) {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let file_id = server.insert(
+            Resource::Files,
+            json!({
+                "application_id": application_id,
+                "name": "clip.mp4",
+                "size": CONTENT.len(),
+                "cloud_status": "disabled",
+            }),
+        );
+
+        let dir = env::temp_dir().join(format!("lumeo-upload-{file_id}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clip.mp4");
+        std::fs::write(&path, CONTENT).unwrap();
+
+        (server, client, file_id, path, ChunkedUpload::new(dir).chunk_size(4))
+    }
+
+    fn chunk_offsets(server: &MockServer) -> Vec<String> {
+        let requests = server.requests();
+        let chunks = requests.iter().filter(|request| request.path.ends_with("/chunks"));
+        chunks.filter_map(|request| request.query.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn resumes_from_acknowledged_offset() {
+        let (server, client, file_id, path, upload) = setup().await;
+        let completion = ErrorInjection::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+            .method(Method::POST)
+            .path("/v1/apps/")
+            .times(1);
+        server.inject_error(completion);
+
+        assert!(client.upload_file_chunked(file_id, &path, &upload).await.is_err());
+        assert_eq!(chunk_offsets(&server), ["offset=0", "offset=4", "offset=8"]);
+        assert_eq!(
+            client.read_file(file_id).await.unwrap().cloud_status,
+            FileCloudStatus::Uploading
+        );
+
+        // Simulate a restart after the first chunk was acknowledged.
+        let progress = serde_json::to_vec(&json!({ "size": CONTENT.len(), "offset": 4 })).unwrap();
+        std::fs::write(upload.progress_path(file_id), progress).unwrap();
+        server.clear_requests();
+
+        client.upload_file_chunked(file_id, &path, &upload).await.unwrap();
+
+        assert_eq!(chunk_offsets(&server), ["offset=4", "offset=8"]);
+        assert_eq!(
+            client.read_file(file_id).await.unwrap().cloud_status,
+            FileCloudStatus::Uploaded
+        );
+        assert!(!upload.progress_path(file_id).exists());
+        let data: Vec<Bytes> =
+            futures::TryStreamExt::try_collect(client.download_file_data(file_id).await.unwrap())
+                .await
+                .unwrap();
+        assert_eq!(data.concat(), CONTENT);
+    }
+
+    #[tokio::test]
+    async fn restarts_after_checksum_mismatch() {
+        let (server, client, file_id, path, upload) = setup().await;
+        let completion = ErrorInjection::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+            .method(Method::POST)
+            .path("/v1/apps/")
+            .times(1);
+        server.inject_error(completion);
+        assert!(client.upload_file_chunked(file_id, &path, &upload).await.is_err());
+
+        // The server has the previous content, which no longer matches the file.
+        std::fs::write(&path, b"abcdefghij").unwrap();
+        let err = client.upload_file_chunked(file_id, &path, &upload).await.unwrap_err();
+        assert!(matches!(err, Error::Api(ApiError::ValidationFailed { .. }, _)));
+        assert!(!upload.progress_path(file_id).exists());
+
+        server.clear_requests();
+        client.upload_file_chunked(file_id, &path, &upload).await.unwrap();
+        assert_eq!(chunk_offsets(&server), ["offset=0", "offset=4", "offset=8"]);
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::Path;
+
+use futures::TryStreamExt;
+use serde_json::Value;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use super::{temp_path, File, ListParams};
+use crate::{error::Error, AppClient, Client, Result};
+
+/// Format of exported file records, see [`AppClient::export_files_to`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row, empty fields for missing values
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Columns of CSV exports, in the order of the fields of [`File`].
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "created_at",
+    "size",
+    "duration",
+    "cloud_status",
+    "gateway_id",
+    "local_path",
+    "application_id",
+    "pipeline_id",
+    "node_id",
+    "deployment_id",
+    "camera_id",
+    "stream_id",
+    "data_url",
+    "metadata_url",
+];
+
+impl AppClient<'_> {
+    /// Writes all files matching `params` to a new file at `path` and returns their number.
+    ///
+    /// Records are fetched page by page and written newest first, so `params` must not set
+    /// `sort_by` or `sort_order` ([`Error::SortedExport`]). They are written to a temporary file
+    /// next to `path`, which replaces `path` once all records are written.
+    pub async fn export_files_to(
+        &self,
+        params: ListParams,
+        format: ExportFormat,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        let client = self.client();
+        if params.sort_by.is_some() || params.sort_order.is_some() {
+            return Err(client.through_cb(Error::SortedExport));
+        }
+
+        let path = path.as_ref();
+        let temp_path = temp_path(path);
+        let count = match self.write_export(params, format, &temp_path).await {
+            Ok(count) => count,
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(err);
+            }
+        };
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .map_err(|source| client.through_cb(Error::Io { path: path.into(), source }))?;
+
+        Ok(count)
+    }
+
+    async fn write_export(
+        &self,
+        params: ListParams,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<u64> {
+        let client = self.client();
+        let io_error = |source| client.through_cb(Error::Io { path: path.into(), source });
+
+        let file = tokio::fs::File::create(path).await.map_err(io_error)?;
+        let mut writer = BufWriter::new(file);
+        if format == ExportFormat::Csv {
+            writer
+                .write_all(csv_row(CSV_COLUMNS.iter().copied()).as_bytes())
+                .await
+                .map_err(io_error)?;
+        }
+
+        let files = self.list_files_stream(params);
+        futures::pin_mut!(files);
+        let mut count = 0;
+        while let Some(file) = files.try_next().await? {
+            let line = match format {
+                ExportFormat::Csv => csv_record(&file),
+                ExportFormat::Ndjson => {
+                    serde_json::to_string(&file).expect("Failed to serialize JSON") + "\n"
+                }
+            };
+            writer.write_all(line.as_bytes()).await.map_err(io_error)?;
+            count += 1;
+        }
+        writer.flush().await.map_err(io_error)?;
+
+        Ok(count)
+    }
+}
+
+impl Client {
+    pub async fn export_files_to(
+        &self,
+        params: ListParams,
+        format: ExportFormat,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        self.default_app()?.export_files_to(params, format, path).await
+    }
+}
+
+fn csv_record(file: &File) -> String {
+    let record = serde_json::to_value(file).expect("Failed to serialize JSON");
+    let fields: Vec<String> = CSV_COLUMNS
+        .iter()
+        .map(|column| match &record[column] {
+            Value::Null => String::new(),
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+        .collect();
+    csv_row(fields.iter().map(String::as_str))
+}
+
+/// Joins `fields` to a CSV row, quoting them as needed (RFC 4180).
+fn csv_row<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        })
+        .collect();
+    fields.join(",") + "\r\n"
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::env;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        files::{FileCloudStatus, FileSortKey, SortOrder},
+        testing::{ErrorInjection, MockServer, Resource},
+    };
+
+    async fn setup() -> (MockServer, Client, Vec<Uuid>) {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let files = [
+            ("2022-06-01T10:00:00Z", "entrance.mp4", 2_000, Some(30), "uploaded", None),
+            ("2022-06-01T11:00:00Z", "lobby, east.mp4", 500, Some(10), "uploaded", None),
+            ("2022-06-01T12:00:00Z", "lobby.jpg", 100, None, "disabled", Some("/var/lobby.jpg")),
+            (
+                "2022-06-01T13:00:00Z",
+                "parking.mp4",
+                8_000,
+                Some(60),
+                "disabled",
+                Some("/var/p.mp4"),
+            ),
+        ];
+        let ids = files
+            .iter()
+            .map(|(created_at, name, size, duration, cloud_status, local_path)| {
+                server.insert(
+                    Resource::Files,
+                    json!({
+                        "application_id": application_id,
+                        "created_at": created_at,
+                        "name": name,
+                        "size": size,
+                        "duration": duration,
+                        "cloud_status": cloud_status,
+                        "local_path": local_path,
+                    }),
+                )
+            })
+            .collect();
+        (server, client, ids)
+    }
+
+    #[tokio::test]
+    async fn filters_and_sorts() {
+        let (_server, client, ids) = setup().await;
+        let list = |params: ListParams| {
+            let client = &client;
+            async move {
+                let files = client.list_files(Some(&params)).await.unwrap();
+                files.into_iter().map(|file| file.id).collect::<Vec<_>>()
+            }
+        };
+
+        let params =
+            ListParams { min_size: Some(500), max_size: Some(2_000), ..Default::default() };
+        assert_eq!(list(params).await, [ids[1], ids[0]]);
+
+        let params = ListParams {
+            cloud_statuses: vec![FileCloudStatus::Uploaded],
+            min_duration: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(list(params).await, [ids[0]]);
+
+        let params = ListParams {
+            name_pattern: Some("lobby*".into()),
+            has_local_path: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(list(params).await, [ids[1]]);
+
+        let params = ListParams {
+            sort_by: Some(FileSortKey::Size),
+            sort_order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        assert_eq!(list(params).await, [ids[2], ids[1], ids[0], ids[3]]);
+    }
+
+    #[tokio::test]
+    async fn exports_records() {
+        let (_server, client, ids) = setup().await;
+        let path = env::temp_dir().join(format!("lumeo-export-{}", Uuid::new_v4()));
+        let params =
+            ListParams { cloud_statuses: vec![FileCloudStatus::Uploaded], ..Default::default() };
+
+        let count = client.export_files_to(params.clone(), ExportFormat::Csv, &path).await.unwrap();
+        assert_eq!(count, 2);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<&str> = csv.split_terminator("\r\n").collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], CSV_COLUMNS.join(","));
+        assert!(rows[1].starts_with(&format!("{},\"lobby, east.mp4\",", ids[1])));
+        assert!(rows[2].contains(",2000,30,uploaded,,,"));
+
+        client.export_files_to(params.clone(), ExportFormat::Ndjson, &path).await.unwrap();
+        let ndjson = std::fs::read_to_string(&path).unwrap();
+        let names: Vec<String> =
+            ndjson.lines().map(|line| serde_json::from_str::<File>(line).unwrap().name).collect();
+        assert_eq!(names, ["lobby, east.mp4", "entrance.mp4"]);
+        std::fs::remove_file(&path).unwrap();
+
+        let params = ListParams { sort_by: Some(FileSortKey::Name), ..params };
+        let err = client.export_files_to(params, ExportFormat::Csv, &path).await.unwrap_err();
+        assert!(matches!(err, Error::SortedExport));
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn keeps_previous_export_on_failure() {
+        let (server, client, _ids) = setup().await;
+        let path = env::temp_dir().join(format!("lumeo-export-{}", Uuid::new_v4()));
+        std::fs::write(&path, "previous").unwrap();
+        server.inject_error(ErrorInjection::new(StatusCode::FORBIDDEN, "forbidden"));
+
+        assert!(client
+            .export_files_to(ListParams::default(), ExportFormat::Csv, &path)
+            .await
+            .is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous");
+        let part =
+            path.with_file_name(format!("{}.part", path.file_name().unwrap().to_str().unwrap()));
+        assert!(!part.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use futures::{future, TryStreamExt};
+use tokio::fs;
+use uuid::Uuid;
+
+use super::{File, FileCloudStatus, ListParams};
+use crate::{error::Error, GatewayClient, Result};
+
+/// Settings of local file synchronization, see [`GatewayClient::sync_local_files`].
+#[derive(Clone, Debug)]
+pub struct LocalFileSync {
+    dir: PathBuf,
+    retention: Option<Duration>,
+    max_files: Option<u64>,
+    dry_run: bool,
+}
+
+impl LocalFileSync {
+    /// Synchronizes the files in `dir`, which must be spelled like in the `local_path` of the
+    /// file records, e.g. the `path` of local clip or snapshot properties.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), retention: None, max_files: None, dry_run: false }
+    }
+
+    /// Deletes local copies of files created more than `retention` ago.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Keeps at most `max_files` local copies, deleting the oldest ones, like `max_edge_files`.
+    pub fn max_files(mut self, max_files: u64) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+
+    /// Only reports what would be done, without uploading or deleting anything.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Actions of a synchronization, planned ones in a dry run.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Files whose pending upload was completed
+    pub uploaded: Vec<Uuid>,
+    /// Local copies deleted because of the retention or the file cap
+    pub deleted_local: Vec<PathBuf>,
+    /// Records of files that exist neither locally nor in the cloud
+    pub deleted_records: Vec<Uuid>,
+    /// Files an action failed for, they are retried by the next synchronization
+    pub failed: Vec<(Uuid, Error)>,
+}
+
+impl SyncReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl GatewayClient<'_> {
+    /// Reconciles the local files in a directory with the file records of the gateway.
+    ///
+    /// Only files with a record whose `local_path` is in the directory are considered, in this
+    /// order:
+    ///
+    /// 1. Files whose upload is pending (cloud status `Uploading`) are uploaded.
+    /// 2. Local copies past the retention or beyond the file cap, oldest first, are deleted.
+    ///    Copies whose upload failed are kept.
+    /// 3. Records that aren't uploaded and whose local copy is gone are deleted.
+    ///
+    /// Listing the directory or the records fails the synchronization, other failures are
+    /// collected in the report.
+    pub async fn sync_local_files(&self, sync: &LocalFileSync) -> Result<SyncReport> {
+        let app = self.app();
+        let client = self.client();
+        let io_error = |source| client.through_cb(Error::Io { path: sync.dir.clone(), source });
+
+        let local_paths = list_dir(&sync.dir).await.map_err(io_error)?;
+        let params = ListParams { gateway_ids: vec![self.gateway_id()], ..Default::default() };
+        // Newest first, the order in which the file cap keeps local copies.
+        let records: Vec<File> = app
+            .list_files_stream(params)
+            .try_filter(|file| {
+                let in_dir = file.local_path.as_deref().map(Path::new).and_then(Path::parent);
+                future::ready(in_dir == Some(&sync.dir))
+            })
+            .try_collect()
+            .await?;
+
+        let mut report = SyncReport::default();
+        let mut kept = HashSet::new();
+        for file in records.iter().filter(|file| local_paths.contains(&local_path(file))) {
+            if file.cloud_status != FileCloudStatus::Uploading {
+                continue;
+            }
+            if !sync.dry_run {
+                if let Err(err) = app.upload_file_content_from(file.id, local_path(file)).await {
+                    report.failed.push((file.id, err));
+                    kept.insert(file.id);
+                    continue;
+                }
+            }
+            report.uploaded.push(file.id);
+        }
+
+        let now = Utc::now();
+        let expired = |file: &File| {
+            sync.retention.map_or(false, |retention| {
+                (now - file.created_at).to_std().map_or(false, |age| age > retention)
+            })
+        };
+        let mut retained = 0;
+        let mut remaining = HashSet::new();
+        for file in records.iter().filter(|file| local_paths.contains(&local_path(file))) {
+            let over_cap = sync.max_files.map_or(false, |max_files| retained >= max_files);
+            if kept.contains(&file.id) || !(expired(file) || over_cap) {
+                retained += 1;
+                remaining.insert(file.id);
+                continue;
+            }
+
+            let path = local_path(file);
+            if !sync.dry_run {
+                if let Err(source) = remove_file(&path).await {
+                    let err = client.through_cb(Error::Io { path, source });
+                    report.failed.push((file.id, err));
+                    remaining.insert(file.id);
+                    continue;
+                }
+            }
+            report.deleted_local.push(path);
+        }
+
+        for file in records.iter().filter(|file| {
+            file.cloud_status != FileCloudStatus::Uploaded
+                && !remaining.contains(&file.id)
+                && !report.uploaded.contains(&file.id)
+        }) {
+            if !sync.dry_run {
+                if let Err(err) = app.delete_file(file.id).await {
+                    report.failed.push((file.id, err));
+                    continue;
+                }
+            }
+            report.deleted_records.push(file.id);
+        }
+
+        Ok(report)
+    }
+}
+
+fn local_path(file: &File) -> PathBuf {
+    file.local_path.as_deref().map(PathBuf::from).unwrap_or_default()
+}
+
+/// Returns the paths of the regular files in `dir`.
+async fn list_dir(dir: &Path) -> io::Result<HashSet<PathBuf>> {
+    let mut paths = HashSet::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            paths.insert(entry.path());
+        }
+    }
+    Ok(paths)
+}
+
+async fn remove_file(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::env;
+
+    use chrono::{DateTime, Duration as ChronoDuration};
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        testing::{ErrorInjection, MockServer, Resource},
+        Client,
+    };
+
+    struct Setup {
+        server: MockServer,
+        client: Client,
+        dir: PathBuf,
+    }
+
+    impl Setup {
+        async fn new() -> Self {
+            let server = MockServer::start().await.unwrap();
+            let client = server
+                .client_builder()
+                .application_id(Uuid::new_v4())
+                .gateway_id(Uuid::new_v4())
+                .build()
+                .unwrap();
+            let dir = env::temp_dir().join(format!("lumeo-sync-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self { server, client, dir }
+        }
+
+        fn gateway(&self) -> GatewayClient<'_> {
+            self.client.default_gateway().unwrap()
+        }
+
+        /// Inserts a record of a file created `age` ago, with a local copy if `local`.
+        fn file(&self, name: &str, age: i64, cloud_status: &str, local: bool) -> Uuid {
+            let path = self.dir.join(name);
+            if local {
+                std::fs::write(&path, name).unwrap();
+            }
+            let created_at: DateTime<Utc> = Utc::now() - ChronoDuration::minutes(age);
+            self.server.insert(
+                Resource::Files,
+                json!({
+                    "application_id": self.client.application_id,
+                    "gateway_id": self.client.gateway_id,
+                    "created_at": created_at,
+                    "name": name,
+                    "size": name.len(),
+                    "cloud_status": cloud_status,
+                    "local_path": path,
+                }),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn enforces_retention() {
+        let setup = Setup::new().await;
+        let fresh = setup.file("fresh.mp4", 1, "disabled", true);
+        let pending = setup.file("pending.mp4", 2, "uploading", true);
+        let over_cap = setup.file("over_cap.mp4", 3, "uploaded", true);
+        let expired = setup.file("expired.mp4", 120, "disabled", true);
+        let orphaned = setup.file("orphaned.mp4", 4, "disabled", false);
+        let in_cloud = setup.file("in_cloud.mp4", 5, "uploaded", false);
+        std::fs::write(setup.dir.join("unknown.mp4"), "unknown").unwrap();
+        let sync = LocalFileSync::new(&setup.dir)
+            .retention(Duration::from_secs(3600))
+            .max_files(2)
+            .dry_run(true);
+
+        let report = setup.gateway().sync_local_files(&sync).await.unwrap();
+
+        assert_eq!(report.uploaded, [pending]);
+        assert_eq!(
+            report.deleted_local,
+            [setup.dir.join("over_cap.mp4"), setup.dir.join("expired.mp4")]
+        );
+        assert_eq!(report.deleted_records, [orphaned, expired]);
+        assert_eq!(setup.server.records(Resource::Files).len(), 6);
+        assert!(setup.dir.join("expired.mp4").exists());
+
+        let report = setup.gateway().sync_local_files(&sync.dry_run(false)).await.unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(report.deleted_records, [orphaned, expired]);
+        let mut remaining: Vec<Uuid> = setup
+            .server
+            .records(Resource::Files)
+            .iter()
+            .map(|file| file["id"].as_str().unwrap().parse().unwrap())
+            .collect();
+        remaining.sort();
+        let mut expected = vec![fresh, pending, over_cap, in_cloud];
+        expected.sort();
+        assert_eq!(remaining, expected);
+        let file = setup.client.read_file(pending).await.unwrap();
+        assert_eq!(file.cloud_status, FileCloudStatus::Uploaded);
+        let mut local: Vec<_> = std::fs::read_dir(&setup.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        local.sort();
+        assert_eq!(local, ["fresh.mp4", "pending.mp4", "unknown.mp4"]);
+        let deletions = setup.server.requests().into_iter().filter(|r| r.method == Method::DELETE);
+        assert_eq!(deletions.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_record_deletions() {
+        let setup = Setup::new().await;
+        let forbidden = setup.file("forbidden.mp4", 1, "disabled", false);
+        let orphaned = setup.file("orphaned.mp4", 2, "disabled", false);
+        let application_id = setup.client.application_id.unwrap();
+        setup.server.inject_error(
+            ErrorInjection::new(StatusCode::FORBIDDEN, "forbidden")
+                .method(Method::DELETE)
+                .path(&format!("/v1/apps/{application_id}/files/{forbidden}")),
+        );
+
+        let report = setup.gateway().sync_local_files(&LocalFileSync::new(&setup.dir)).await;
+
+        let report = report.unwrap();
+        assert_eq!(report.deleted_records, [orphaned]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, forbidden);
+        assert_eq!(setup.server.records(Resource::Files).len(), 1);
+    }
+}
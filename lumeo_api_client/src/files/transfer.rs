@@ -0,0 +1,278 @@
+use std::path::Path;
+
+use bytes::Bytes;
+use futures::{Stream, TryStreamExt};
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use url::Url;
+use uuid::Uuid;
+
+use super::{temp_path, File, FileCloudStatus};
+use crate::{error::Error, AppClient, Client, Result};
+
+impl<'a> AppClient<'a> {
+    /// Uploads the content of the file, streaming it from `content`.
+    ///
+    /// The cloud status of the file is set to `Uploading` before and to `Uploaded` after the
+    /// upload. If the upload fails, the file stays `Uploading`. Streamed uploads aren't retried.
+    pub async fn upload_file_content(
+        &self,
+        file_id: Uuid,
+        content: impl AsyncRead + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.update_cloud_status(file_id, &FileCloudStatus::Uploading).await?;
+
+        let application_id = self.application_id();
+        let path = format!("/v1/apps/{application_id}/files/{file_id}/data");
+        let body = reqwest::Body::wrap_stream(ReaderStream::new(content));
+        let client = self.client();
+        client.put_body(&path, None, body, client.transfer_timeout).await?;
+
+        self.update_cloud_status(file_id, &FileCloudStatus::Uploaded).await
+    }
+
+    /// Uploads the file at `path` as content of the file, see
+    /// [`upload_file_content`](Self::upload_file_content).
+    pub async fn upload_file_content_from(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let content = tokio::fs::File::open(path)
+            .await
+            .map_err(|source| self.client().through_cb(Error::Io { path: path.into(), source }))?;
+        self.upload_file_content(file_id, content).await
+    }
+
+    /// Downloads the data of the file from its `data_url`.
+    pub async fn download_file_data(
+        self,
+        file_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + 'a> {
+        let file = self.read_file(file_id).await?;
+        self.client().download(content_url(&file, file.data_url.as_ref(), "data")?).await
+    }
+
+    /// Downloads the metadata of the file from its `metadata_url`.
+    pub async fn download_file_metadata(
+        self,
+        file_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + 'a> {
+        let file = self.read_file(file_id).await?;
+        self.client().download(content_url(&file, file.metadata_url.as_ref(), "metadata")?).await
+    }
+
+    /// Downloads the data of the file to `path` and returns its size.
+    pub async fn download_file_data_to(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        let content = self.download_file_data(file_id).await?;
+        self.client().write_to(content, path.as_ref()).await
+    }
+
+    /// Downloads the metadata of the file to `path` and returns its size.
+    pub async fn download_file_metadata_to(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        let content = self.download_file_metadata(file_id).await?;
+        self.client().write_to(content, path.as_ref()).await
+    }
+}
+
+fn content_url<'a>(file: &File, url: Option<&'a Url>, kind: &'static str) -> Result<&'a Url> {
+    url.ok_or(Error::FileContentMissing { file_id: file.id, kind })
+}
+
+impl Client {
+    /// Writes the downloaded `content` to a temporary file next to `path`, which replaces `path`
+    /// once the download is complete.
+    async fn write_to(
+        &self,
+        content: impl Stream<Item = Result<Bytes>>,
+        path: &Path,
+    ) -> Result<u64> {
+        let temp_path = temp_path(path);
+        let size = match self.write_content(content, &temp_path).await {
+            Ok(size) => size,
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(err);
+            }
+        };
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .map_err(|source| self.through_cb(Error::Io { path: path.into(), source }))?;
+
+        Ok(size)
+    }
+
+    async fn write_content(
+        &self,
+        content: impl Stream<Item = Result<Bytes>>,
+        path: &Path,
+    ) -> Result<u64> {
+        let io_error = |source| self.through_cb(Error::Io { path: path.into(), source });
+        let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+
+        futures::pin_mut!(content);
+        let mut size = 0;
+        while let Some(chunk) = content.try_next().await? {
+            file.write_all(&chunk).await.map_err(io_error)?;
+            size += chunk.len() as u64;
+        }
+        file.flush().await.map_err(io_error)?;
+
+        Ok(size)
+    }
+
+    pub async fn upload_file_content(
+        &self,
+        file_id: Uuid,
+        content: impl AsyncRead + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.default_app()?.upload_file_content(file_id, content).await
+    }
+
+    pub async fn upload_file_content_from(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        self.default_app()?.upload_file_content_from(file_id, path).await
+    }
+
+    pub async fn download_file_data(
+        &self,
+        file_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + '_> {
+        self.default_app()?.download_file_data(file_id).await
+    }
+
+    pub async fn download_file_metadata(
+        &self,
+        file_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + '_> {
+        self.default_app()?.download_file_metadata(file_id).await
+    }
+
+    pub async fn download_file_data_to(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        self.default_app()?.download_file_data_to(file_id, path).await
+    }
+
+    pub async fn download_file_metadata_to(
+        &self,
+        file_id: Uuid,
+        path: impl AsRef<Path>,
+    ) -> Result<u64> {
+        self.default_app()?.download_file_metadata_to(file_id, path).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{MockServer, Resource};
+
+    #[tokio::test]
+    async fn uploads_and_downloads_content() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let file_id = server.insert(
+            Resource::Files,
+            json!({
+                "application_id": application_id,
+                "name": "clip.mp4",
+                "size": 12,
+                "cloud_status": "disabled",
+            }),
+        );
+
+        client.upload_file_content(file_id, &b"clip content"[..]).await.unwrap();
+        let file = client.read_file(file_id).await.unwrap();
+        assert_eq!(file.cloud_status, FileCloudStatus::Uploaded);
+        let statuses: Vec<_> = server
+            .requests()
+            .iter()
+            .filter(|request| request.path.ends_with("/cloud_status"))
+            .map(|request| request.text())
+            .collect();
+        assert_eq!(statuses, ["uploading", "uploaded"]);
+
+        let data: Vec<Bytes> =
+            client.download_file_data(file_id).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(data.concat(), b"clip content");
+
+        let path = env::temp_dir().join(format!("{file_id}.mp4"));
+        assert_eq!(client.download_file_data_to(file_id, &path).await.unwrap(), 12);
+        assert_eq!(std::fs::read(&path).unwrap(), b"clip content");
+        std::fs::remove_file(path).unwrap();
+
+        // The download URL is signed, the API token isn't sent to storage.
+        let download = server.requests().pop().unwrap();
+        assert!(!download.headers.contains_key(reqwest::header::AUTHORIZATION));
+
+        let err = client.download_file_metadata(file_id).await.err().unwrap();
+        assert!(matches!(err, Error::FileContentMissing { kind: "metadata", .. }));
+    }
+
+    #[tokio::test]
+    async fn keeps_previous_file_on_failed_download() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client_builder().build().unwrap();
+        let path = env::temp_dir().join(format!("{}.mp4", Uuid::new_v4()));
+        std::fs::write(&path, "previous").unwrap();
+
+        let content = futures::stream::iter([
+            Ok(Bytes::from_static(b"clip")),
+            Err(Error::ApplicationIdMissing),
+        ]);
+        assert!(client.write_to(content, &path).await.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous");
+        assert!(!temp_path(&path).exists());
+
+        let content = futures::stream::iter([Ok(Bytes::from_static(b"clip content"))]);
+        assert_eq!(client.write_to(content, &path).await.unwrap(), 12);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "clip content");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn download_errors_hide_signature() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let file_id = server.insert(
+            Resource::Files,
+            json!({
+                "application_id": application_id,
+                "name": "clip.mp4",
+                "size": 12,
+                "cloud_status": "uploaded",
+                "data_url": format!("{}/storage/missing/data?signature=secret", server.url()),
+            }),
+        );
+
+        let err = client.download_file_data(file_id).await.err().unwrap();
+        assert!(matches!(err, Error::Reqwest(..)));
+        let mut source: Option<&dyn std::error::Error> = Some(&err);
+        while let Some(err) = source {
+            assert!(!err.to_string().contains("secret"), "{err}");
+            source = err.source();
+        }
+        assert!(!format!("{err:?}").contains("secret"));
+    }
+}
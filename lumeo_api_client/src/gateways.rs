@@ -6,7 +6,7 @@ use serde_with::skip_serializing_none;
 use uuid::Uuid;
 
 use super::Client;
-use crate::{cameras::Camera, Result};
+use crate::{cameras::Camera, GatewayClient, Result, Secret};
 
 #[skip_serializing_none]
 #[derive(Serialize)]
@@ -40,7 +40,7 @@ pub struct Gateway {
     pub ip_ext: Option<String>,
     pub mac_address: Option<String>,
     // Access token is `Some` only in response from `create` route.
-    pub access_token: Option<String>,
+    pub access_token: Option<Secret<String>>,
     pub version: Option<String>,
 }
 
@@ -52,33 +52,63 @@ impl Client {
     ) -> Result<Gateway> {
         self.post(&format!("/v1/apps/{application_id}/gateways"), gateway).await
     }
+}
 
+impl GatewayClient<'_> {
     pub async fn read_gateway(&self) -> Result<Gateway> {
-        let application_id = self.application_id()?;
-        let gateway_id = self.gateway_id()?;
-        self.get(&format!("/v1/apps/{application_id}/gateways/{gateway_id}"), None::<&()>).await
+        let application_id = self.application_id();
+        let gateway_id = self.gateway_id();
+        self.client()
+            .get(&format!("/v1/apps/{application_id}/gateways/{gateway_id}"), None::<&()>)
+            .await
     }
 
     pub async fn list_linked_cameras(&self) -> Result<Vec<Camera>> {
-        let application_id = self.application_id()?;
-        let gateway_id = self.gateway_id()?;
-        self.get(
-            &format!("/v1/apps/{application_id}/gateways/{gateway_id}/linked_cameras"),
-            None::<&()>,
-        )
-        .await
+        let application_id = self.application_id();
+        let gateway_id = self.gateway_id();
+        self.client()
+            .get(
+                &format!("/v1/apps/{application_id}/gateways/{gateway_id}/linked_cameras"),
+                None::<&()>,
+            )
+            .await
     }
 
     pub async fn update_gateway_ip_local(&self, ip: &IpAddr) -> Result<()> {
-        let application_id = self.application_id()?;
-        let gateway_id = self.gateway_id()?;
-        self.put_text(&format!("/v1/apps/{application_id}/gateways/{gateway_id}/ip_local"), ip)
+        let application_id = self.application_id();
+        let gateway_id = self.gateway_id();
+        self.client()
+            .put_text(&format!("/v1/apps/{application_id}/gateways/{gateway_id}/ip_local"), ip)
             .await
     }
 
     pub async fn update_gateway_ip_ext(&self, ip: &IpAddr) -> Result<()> {
-        let application_id = self.application_id()?;
-        let gateway_id = self.gateway_id()?;
-        self.put_text(&format!("/v1/apps/{application_id}/gateways/{gateway_id}/ip_ext"), ip).await
+        let application_id = self.application_id();
+        let gateway_id = self.gateway_id();
+        self.client()
+            .put_text(&format!("/v1/apps/{application_id}/gateways/{gateway_id}/ip_ext"), ip)
+            .await
+    }
+}
+
+impl Client {
+    #[deprecated(note = "Gateway endpoint, use `GatewayClient::read_gateway`")]
+    pub async fn read_gateway(&self) -> Result<Gateway> {
+        self.default_gateway()?.read_gateway().await
+    }
+
+    #[deprecated(note = "Gateway endpoint, use `GatewayClient::list_linked_cameras`")]
+    pub async fn list_linked_cameras(&self) -> Result<Vec<Camera>> {
+        self.default_gateway()?.list_linked_cameras().await
+    }
+
+    #[deprecated(note = "Gateway endpoint, use `GatewayClient::update_gateway_ip_local`")]
+    pub async fn update_gateway_ip_local(&self, ip: &IpAddr) -> Result<()> {
+        self.default_gateway()?.update_gateway_ip_local(ip).await
+    }
+
+    #[deprecated(note = "Gateway endpoint, use `GatewayClient::update_gateway_ip_ext`")]
+    pub async fn update_gateway_ip_ext(&self, ip: &IpAddr) -> Result<()> {
+        self.default_gateway()?.update_gateway_ip_ext(ip).await
     }
 }
@@ -1,12 +1,17 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use error::ResultExt;
-use reqwest::{header, Method, Url};
+use futures::{Stream, TryStreamExt};
+use reqwest::{header, Method, Response, Url};
 use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
 
 pub mod apps;
 pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
 pub mod cameras;
 pub mod commands;
 pub mod deployments;
@@ -15,26 +20,44 @@ pub mod error;
 pub mod events;
 pub mod files;
 pub mod gateways;
+pub mod lumeo_url;
 pub mod metrics;
+pub mod middleware;
 pub mod models;
 pub mod orgs;
+pub mod pagination;
 pub mod pipeline;
+mod retry;
+mod scoped;
+mod secret;
 pub mod snapshots;
 pub mod streams;
-
-use error::{verify_response, Error};
+#[cfg(feature = "testing")]
+pub mod testing;
+
+use auth::{CredentialProvider, StaticToken};
+pub use builder::ClientBuilder;
+use error::{verify_response, ApiError, Error};
+use middleware::{Middleware, RequestInfo};
+pub use retry::RetryPolicy;
+pub use scoped::{AppClient, GatewayClient};
+pub use secret::Secret;
 type Callback = Box<dyn Fn(&Error) + Send + Sync + 'static>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_TRANSFER_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 const DEFAULT_LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct Client {
     http_client: reqwest::Client,
     base_url: String,
-    auth_token: String,
+    credentials: Box<dyn CredentialProvider>,
     application_id: Option<Uuid>,
     gateway_id: Option<Uuid>,
+    retry_policy: RetryPolicy,
+    transfer_timeout: Duration,
+    middlewares: Vec<Box<dyn Middleware>>,
     error_cb: Option<Callback>,
 }
 
@@ -46,16 +69,17 @@ impl Client {
         gateway_id: Option<Uuid>,
         user_agent: Option<&str>,
     ) -> reqwest::Result<Self> {
-        Ok(Self::with_http_client(
-            base_url,
-            auth_token,
+        ClientBuilder {
             application_id,
             gateway_id,
-            reqwest::Client::builder()
-                .timeout(DEFAULT_TIMEOUT)
-                .user_agent(user_agent.unwrap_or("Lumeo api-client"))
-                .build()?,
-        ))
+            user_agent: user_agent.map(Into::into),
+            ..ClientBuilder::new(base_url, auth_token)
+        }
+        .build()
+    }
+
+    pub fn builder(base_url: impl Into<String>, auth_token: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(base_url, auth_token)
     }
 
     pub fn with_http_client(
@@ -65,7 +89,17 @@ impl Client {
         gateway_id: Option<Uuid>,
         http_client: reqwest::Client,
     ) -> Self {
-        Self { http_client, base_url, auth_token, application_id, gateway_id, error_cb: None }
+        Self {
+            http_client,
+            base_url,
+            credentials: Box::new(StaticToken(auth_token)),
+            application_id,
+            gateway_id,
+            retry_policy: RetryPolicy::default(),
+            transfer_timeout: DEFAULT_TRANSFER_TIMEOUT,
+            middlewares: Vec::new(),
+            error_cb: None,
+        }
     }
 
     pub async fn get<T, Q>(&self, path: &str, query: Option<&Q>) -> Result<T>
@@ -85,7 +119,7 @@ impl Client {
             query.map(serde_urlencoded::to_string).transpose().http_context(Method::GET, path)?;
         let request_builder = self.request(Method::GET, path, query.as_deref())?;
 
-        verify_response(request_builder.send().await, Method::GET, path)
+        self.send(Method::GET, path, request_builder)
             .await?
             .json()
             .await
@@ -120,7 +154,7 @@ impl Client {
     {
         let request_builder = self.request(Method::POST, path, None)?.json(body);
 
-        verify_response(request_builder.send().await, Method::POST, path)
+        self.send(Method::POST, path, request_builder)
             .await?
             .json()
             .await
@@ -141,7 +175,7 @@ impl Client {
         T: DeserializeOwned,
     {
         let request_builder = self.request(Method::PUT, path, None)?.json(body);
-        verify_response(request_builder.send().await, Method::PUT, path)
+        self.send(Method::PUT, path, request_builder)
             .await?
             .json()
             .await
@@ -182,9 +216,7 @@ impl Client {
             request_builder = request_builder.header(header::CONTENT_LENGTH, 0)
         }
 
-        verify_response(request_builder.send().await, method, path)
-            .await
-            .map_err(|err| self.through_cb(err))?;
+        self.send(method, path, request_builder).await.map_err(|err| self.through_cb(err))?;
         Ok(())
     }
 
@@ -200,12 +232,57 @@ impl Client {
         R: ToString + ?Sized,
     {
         let request_builder = self.request(Method::PUT, path, None)?;
-        verify_response(request_builder.body(body.to_string()).send().await, Method::PUT, path)
-            .await?;
+        self.send(Method::PUT, path, request_builder.body(body.to_string())).await?;
 
         Ok(())
     }
 
+    /// Sends `body` as is, e.g. file content, and returns the response.
+    ///
+    /// Streaming bodies are never retried.
+    /// Uploads `body`, which may take up to `timeout` instead of the client's timeout.
+    pub(crate) async fn put_body(
+        &self,
+        path: &str,
+        query: Option<&str>,
+        body: impl Into<reqwest::Body>,
+        timeout: Duration,
+    ) -> Result<Response> {
+        let request_builder = self.request(Method::PUT, path, query)?.timeout(timeout);
+        self.send(Method::PUT, path, request_builder.body(body))
+            .await
+            .map_err(|err| self.through_cb(err))
+    }
+
+    /// Downloads from a URL outside the API, e.g. a signed storage URL, without credentials.
+    ///
+    /// The download may take up to the client's transfer timeout.
+    pub(crate) async fn download(
+        &self,
+        url: &Url,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + '_> {
+        // The query of signed URLs is a secret, don't include it in errors.
+        let path = url.path().to_owned();
+        let response = self
+            .http_client
+            .get(url.clone())
+            .timeout(self.transfer_timeout)
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(reqwest::Error::without_url)
+            .http_context(Method::GET, &path)
+            .map_err(|err| self.through_cb(err))?;
+
+        Ok(response.bytes_stream().map_err(move |err| {
+            let status = err.status();
+            self.through_cb(Error::Reqwest(
+                err.without_url(),
+                error::ErrorDetails::new(Method::GET, &path, status),
+            ))
+        }))
+    }
+
     pub async fn delete<Q>(&self, path: &str, query: Option<&Q>) -> Result<()>
     where
         Q: Serialize,
@@ -222,7 +299,7 @@ impl Client {
             .transpose()
             .http_context(Method::DELETE, path)?;
         let request_builder = self.request(Method::DELETE, path, query.as_deref())?;
-        verify_response(request_builder.send().await, Method::DELETE, path).await?;
+        self.send(Method::DELETE, path, request_builder).await?;
 
         Ok(())
     }
@@ -254,10 +331,88 @@ impl Client {
             url.set_query(Some(&full_query));
         }
 
-        Ok(self
-            .http_client
-            .request(method, url)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.auth_token)))
+        Ok(self.http_client.request(method, url))
+    }
+
+    /// Sends the request, retrying it according to the client's [`RetryPolicy`].
+    ///
+    /// If the server rejects the credentials and the [`CredentialProvider`] manages to refresh
+    /// them, the request is replayed once with the new token.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        mut request_builder: reqwest::RequestBuilder,
+    ) -> Result<Response> {
+        let mut token = self.credentials.token().await?;
+        let mut attempt = 0;
+        let mut retries = 0;
+        let mut replayed = false;
+        let first_attempt = Instant::now();
+
+        loop {
+            let info = RequestInfo { method: &method, path, attempt };
+
+            // Requests with streaming bodies can't be cloned and thus are never sent twice.
+            let next_request_builder = request_builder.try_clone();
+            let mut request =
+                request_builder.bearer_auth(&token).build().http_context(method.clone(), path)?;
+
+            for middleware in &self.middlewares {
+                middleware.on_request(&info, &mut request);
+            }
+
+            let start = Instant::now();
+            let response = self.http_client.execute(request).await;
+            // Also covers 503 responses, which carry the header but no `rate-limited` error.
+            let header_retry_after = response.as_ref().ok().and_then(retry::retry_after);
+
+            let err = match verify_response(response, method.clone(), path).await {
+                Ok(response) => {
+                    for middleware in &self.middlewares {
+                        middleware.on_response(&info, &response, start.elapsed());
+                    }
+                    return Ok(response);
+                }
+                Err(err) => {
+                    for middleware in &self.middlewares {
+                        middleware.on_error(&info, &err, start.elapsed());
+                    }
+                    err
+                }
+            };
+
+            request_builder = match next_request_builder {
+                Some(next_request_builder) => next_request_builder,
+                None => return Err(err),
+            };
+
+            if !replayed
+                && matches!(err, Error::Api(ApiError::InvalidCredentials, _))
+                && self.credentials.refresh(&token).await?
+            {
+                token = self.credentials.token().await?;
+                replayed = true;
+            } else if RetryPolicy::is_retryable_method(&method)
+                && retries < self.retry_policy.max_retries
+                && err.is_retryable()
+            {
+                let retry_after = match &err {
+                    Error::Api(api_error, _) => api_error.retry_after(),
+                    _ => None,
+                };
+                let retry_after = retry_after.or(header_retry_after);
+                match self.retry_policy.delay(retries, retry_after, first_attempt.elapsed()) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(err),
+                }
+                retries += 1;
+            } else {
+                return Err(err);
+            }
+
+            attempt += 1;
+        }
     }
 
     pub fn register_error_cb(&mut self, cb: impl Fn(&Error) + Send + Sync + 'static) {
@@ -270,12 +425,4 @@ impl Client {
         }
         err
     }
-
-    fn application_id(&self) -> Result<Uuid> {
-        self.application_id.ok_or(Error::ApplicationIdMissing).map_err(|err| self.through_cb(err))
-    }
-
-    fn gateway_id(&self) -> Result<Uuid> {
-        self.gateway_id.ok_or(Error::GatewayIdMissing).map_err(|err| self.through_cb(err))
-    }
 }
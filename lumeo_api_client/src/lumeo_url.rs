@@ -0,0 +1,237 @@
+//! URLs of the `lumeo` scheme, referring to resources of the Lumeo API or to metadata sources.
+//!
+//! Supported shapes:
+//!
+//! - `lumeo://files/<file_id>`, as well as the legacy `lumeo://<file_id>`
+//! - `lumeo://streams/<stream_id>`
+//! - `lumeo://cameras/<camera_id>`
+//! - `lumeo://<host>:<port>`, a metadata source read over TCP
+
+use std::{fmt, str::FromStr};
+
+use serde::{
+    de::{Deserialize, Deserializer, Error},
+    ser::{Serialize, Serializer},
+};
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+pub const SCHEME: &str = "lumeo";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LumeoUrl {
+    File(Uuid),
+    Stream(Uuid),
+    Camera(Uuid),
+    /// TCP source of metadata, see
+    /// [`MetadataInserterProperties`](crate::pipeline::MetadataInserterProperties)
+    Metadata {
+        host: String,
+        port: u16,
+    },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LumeoUrlError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("Invalid scheme, expected 'lumeo', got '{0}'")]
+    InvalidScheme(String),
+    #[error("Unsupported Lumeo URL: '{0}'")]
+    Unsupported(String),
+    #[error("Invalid UUID: '{0}'")]
+    InvalidUuid(String),
+}
+
+impl LumeoUrl {
+    /// Returns the file ID of `lumeo://files/<file_id>` and legacy `lumeo://<file_id>` URLs.
+    pub fn file_id(&self) -> Option<Uuid> {
+        match self {
+            LumeoUrl::File(file_id) => Some(*file_id),
+            _ => None,
+        }
+    }
+
+    pub fn to_url(&self) -> Url {
+        self.to_string().parse().unwrap_or_else(|error| {
+            unreachable!("Failed to parse generated Lumeo URL with error: {error}")
+        })
+    }
+}
+
+impl TryFrom<&Url> for LumeoUrl {
+    type Error = LumeoUrlError;
+
+    fn try_from(url: &Url) -> Result<Self, Self::Error> {
+        if url.scheme() != SCHEME {
+            return Err(LumeoUrlError::InvalidScheme(url.scheme().into()));
+        }
+        let unsupported = || LumeoUrlError::Unsupported(url.as_str().into());
+        if !url.username().is_empty()
+            || url.password().is_some()
+            || url.query().is_some()
+            || url.fragment().is_some()
+        {
+            return Err(unsupported());
+        }
+
+        let host = url.host_str().ok_or_else(unsupported)?;
+        let parse_id =
+            |id: &str| Uuid::parse_str(id).map_err(|_| LumeoUrlError::InvalidUuid(id.into()));
+        match (host, url.port(), url.path()) {
+            (host, Some(port), "" | "/") if !host.is_empty() => {
+                Ok(LumeoUrl::Metadata { host: host.into(), port })
+            }
+            ("files", None, path) => {
+                Ok(LumeoUrl::File(parse_id(resource_id(path).ok_or_else(unsupported)?)?))
+            }
+            ("streams", None, path) => {
+                Ok(LumeoUrl::Stream(parse_id(resource_id(path).ok_or_else(unsupported)?)?))
+            }
+            ("cameras", None, path) => {
+                Ok(LumeoUrl::Camera(parse_id(resource_id(path).ok_or_else(unsupported)?)?))
+            }
+            (file_id, None, "") if !file_id.is_empty() => Ok(LumeoUrl::File(parse_id(file_id)?)),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Returns `<id>` of a `/<id>` path.
+fn resource_id(path: &str) -> Option<&str> {
+    path.strip_prefix('/').filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
+impl TryFrom<Url> for LumeoUrl {
+    type Error = LumeoUrlError;
+
+    fn try_from(url: Url) -> Result<Self, Self::Error> {
+        LumeoUrl::try_from(&url)
+    }
+}
+
+impl From<LumeoUrl> for Url {
+    fn from(url: LumeoUrl) -> Self {
+        url.to_url()
+    }
+}
+
+impl FromStr for LumeoUrl {
+    type Err = LumeoUrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LumeoUrl::try_from(&Url::parse(s)?)
+    }
+}
+
+impl fmt::Display for LumeoUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LumeoUrl::File(file_id) => write!(f, "{SCHEME}://files/{file_id}"),
+            LumeoUrl::Stream(stream_id) => write!(f, "{SCHEME}://streams/{stream_id}"),
+            LumeoUrl::Camera(camera_id) => write!(f, "{SCHEME}://cameras/{camera_id}"),
+            LumeoUrl::Metadata { host, port } => write!(f, "{SCHEME}://{host}:{port}"),
+        }
+    }
+}
+
+impl Serialize for LumeoUrl {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for LumeoUrl {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        LumeoUrl::from_str(&s).map_err(Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "f65c8128-e25a-11ec-b486-efa3b8212d7f";
+
+    fn id() -> Uuid {
+        Uuid::parse_str(ID).unwrap()
+    }
+
+    #[test]
+    fn parses_and_formats() {
+        let urls = [
+            (format!("lumeo://files/{ID}"), LumeoUrl::File(id())),
+            (format!("lumeo://streams/{ID}"), LumeoUrl::Stream(id())),
+            (format!("lumeo://cameras/{ID}"), LumeoUrl::Camera(id())),
+            (
+                "lumeo://0.0.0.0:5000".into(),
+                LumeoUrl::Metadata { host: "0.0.0.0".into(), port: 5000 },
+            ),
+        ];
+
+        for (s, url) in urls {
+            assert_eq!(s.parse::<LumeoUrl>().unwrap(), url);
+            assert_eq!(url.to_string(), s);
+            assert_eq!(LumeoUrl::try_from(url.to_url()).unwrap(), url);
+        }
+    }
+
+    #[test]
+    fn parses_metadata_url_with_root_path() {
+        let url: LumeoUrl = "lumeo://host:5000/".parse().unwrap();
+
+        assert_eq!(url, LumeoUrl::Metadata { host: "host".into(), port: 5000 });
+        assert_eq!(url.to_string(), "lumeo://host:5000");
+    }
+
+    #[test]
+    fn parses_legacy_file_url() {
+        let url: LumeoUrl = format!("lumeo://{ID}").parse().unwrap();
+
+        assert_eq!(url.file_id(), Some(id()));
+        assert_eq!(url.to_string(), format!("lumeo://files/{ID}"));
+        assert_eq!(
+            LumeoUrl::try_from(crate::files::create_lumeo_file_url(id())).unwrap(),
+            LumeoUrl::File(id())
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_urls() {
+        let parse = |s: &str| s.parse::<LumeoUrl>().unwrap_err();
+
+        assert_eq!(parse("http://example.com"), LumeoUrlError::InvalidScheme("http".into()));
+        assert!(matches!(parse("not a url"), LumeoUrlError::InvalidUrl(_)));
+        assert_eq!(parse("lumeo://files/123"), LumeoUrlError::InvalidUuid("123".into()));
+        assert_eq!(parse("lumeo://123"), LumeoUrlError::InvalidUuid("123".into()));
+        for s in [
+            "lumeo://".to_owned(),
+            "lumeo://files".to_owned(),
+            format!("lumeo://files/{ID}/data"),
+            format!("lumeo://models/{ID}"),
+            format!("lumeo://files/{ID}?key=value"),
+            "lumeo://0.0.0.0:5000/path".to_owned(),
+        ] {
+            assert!(matches!(parse(&s), LumeoUrlError::Unsupported(_)), "{s}");
+        }
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let json = serde_json::to_value(LumeoUrl::Stream(id())).unwrap();
+        assert_eq!(json, format!("lumeo://streams/{ID}"));
+
+        let url: LumeoUrl = serde_json::from_value(format!("lumeo://{ID}").into()).unwrap();
+        assert_eq!(url, LumeoUrl::File(id()));
+        assert!(serde_json::from_value::<LumeoUrl>("lumeo://".into()).is_err());
+    }
+}
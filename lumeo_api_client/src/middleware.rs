@@ -0,0 +1,187 @@
+//! Hooks around every request sent by [`Client`](crate::Client).
+//!
+//! Middlewares are registered with [`ClientBuilder::middleware`](crate::ClientBuilder::middleware)
+//! and run in registration order.
+//!
+//! ```no_run
+//! use lumeo_api_client::{
+//!     middleware::{LogRequests, TracePropagation},
+//!     ClientBuilder,
+//! };
+//!
+//! let client = ClientBuilder::new("https://api.lumeo.com", "token")
+//!     .middleware(TracePropagation)
+//!     .middleware(LogRequests)
+//!     .build()?;
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::{future::Future, time::Duration};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Method, Request, Response,
+};
+
+use crate::{commands::TraceHeaders, error::Error};
+
+/// The request a middleware hook is called for.
+#[derive(Clone, Copy, Debug)]
+pub struct RequestInfo<'a> {
+    pub method: &'a Method,
+    /// API path, without the base URL and query
+    pub path: &'a str,
+    /// Zero for the first attempt, incremented for each retry or replay
+    pub attempt: u32,
+}
+
+pub trait Middleware: Send + Sync {
+    /// Called before each attempt of a request is sent, e.g. to add headers.
+    fn on_request(&self, info: &RequestInfo<'_>, request: &mut Request) {
+        let _ = (info, request);
+    }
+
+    /// Called when an attempt succeeded. `elapsed` is the time until response headers arrived.
+    fn on_response(&self, info: &RequestInfo<'_>, response: &Response, elapsed: Duration) {
+        let _ = (info, response, elapsed);
+    }
+
+    /// Called when an attempt failed, including attempts that are going to be retried.
+    fn on_error(&self, info: &RequestInfo<'_>, error: &Error, elapsed: Duration) {
+        let _ = (info, error, elapsed);
+    }
+}
+
+/// Logs every attempt of a request with its method, path, status and latency, using the [`log`]
+/// crate.
+///
+/// Successful attempts are logged at `debug` level and failed ones at `warn` level.
+pub struct LogRequests;
+
+impl Middleware for LogRequests {
+    fn on_response(&self, info: &RequestInfo<'_>, response: &Response, elapsed: Duration) {
+        log::debug!(
+            "{} {} -> {} in {elapsed:?} (attempt {})",
+            info.method,
+            info.path,
+            response.status(),
+            info.attempt
+        );
+    }
+
+    fn on_error(&self, info: &RequestInfo<'_>, error: &Error, elapsed: Duration) {
+        let status = error.status().map_or_else(|| "error".to_owned(), |status| status.to_string());
+        log::warn!(
+            "{} {} -> {status} in {elapsed:?} (attempt {}): {error}",
+            info.method,
+            info.path,
+            info.attempt
+        );
+    }
+}
+
+tokio::task_local! {
+    static TRACE_HEADERS: TraceHeaders;
+}
+
+/// Runs `future` with `trace_headers` attached to every API request it makes, provided the client
+/// has the [`TracePropagation`] middleware.
+///
+/// This is meant to wrap the handling of a [`commands::Message`](crate::commands::Message), so
+/// that API calls can be correlated with the command that triggered them.
+pub async fn with_trace_headers<F: Future>(trace_headers: TraceHeaders, future: F) -> F::Output {
+    TRACE_HEADERS.scope(trace_headers, future).await
+}
+
+/// Propagates the trace context set by [`with_trace_headers`], e.g. the W3C `traceparent` and
+/// `tracestate` headers, to API requests.
+pub struct TracePropagation;
+
+impl Middleware for TracePropagation {
+    fn on_request(&self, _info: &RequestInfo<'_>, request: &mut Request) {
+        let _ = TRACE_HEADERS.try_with(|trace_headers| {
+            for (name, value) in trace_headers.as_iter_str() {
+                // Invalid headers can't be sent, and failing the request because of them
+                // would be worse than losing the trace.
+                if let (Ok(name), Ok(value)) =
+                    (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value))
+                {
+                    request.headers_mut().insert(name, value);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use log::{LevelFilter, Log, Metadata, Record};
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::error::ErrorDetails;
+
+    struct TestLogger(Mutex<Vec<String>>);
+
+    impl Log for TestLogger {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            self.0.lock().unwrap().push(format!("{} {}", record.level(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+
+    const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    fn propagate() -> Request {
+        let mut request = Request::new(Method::GET, "http://localhost/v1/orgs".parse().unwrap());
+        let info = RequestInfo { method: &Method::GET, path: "/v1/orgs", attempt: 0 };
+        TracePropagation.on_request(&info, &mut request);
+        request
+    }
+
+    #[tokio::test]
+    async fn propagates_trace_headers_in_scope() {
+        let trace_headers = TraceHeaders(vec![
+            ("traceparent".to_owned(), TRACEPARENT.to_owned()),
+            ("invalid header".to_owned(), "value".to_owned()),
+        ]);
+
+        let request = with_trace_headers(trace_headers, async { propagate() }).await;
+
+        assert_eq!(request.headers().get("traceparent").unwrap(), TRACEPARENT);
+        assert_eq!(request.headers().len(), 1);
+    }
+
+    #[test]
+    fn ignores_requests_outside_scope() {
+        assert!(propagate().headers().is_empty());
+    }
+
+    #[test]
+    fn logs_failed_requests() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Debug);
+        let info = RequestInfo { method: &Method::GET, path: "/v1/orgs", attempt: 1 };
+        let details =
+            ErrorDetails::new(Method::GET, "/v1/orgs", Some(StatusCode::SERVICE_UNAVAILABLE));
+
+        LogRequests.on_error(&info, &Error::ApiEmptyResponse(details), Duration::from_millis(5));
+
+        let logs = LOGGER.0.lock().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(
+            logs[0].starts_with("WARN GET /v1/orgs -> 503 Service Unavailable in 5ms (attempt 1)"),
+            "{}",
+            logs[0]
+        );
+    }
+}
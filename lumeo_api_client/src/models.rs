@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::Client;
-use crate::{pipeline::Resolution, Result};
+use crate::{pipeline::Resolution, AppClient, Result};
 
 #[derive(Debug, Deserialize)]
 pub struct Model {
@@ -163,10 +163,18 @@ pub enum ClusterMode {
     NoClustering,
 }
 
+impl AppClient<'_> {
+    pub async fn read_model(&self, model_id: Uuid) -> Result<Model> {
+        let application_id = self.application_id();
+        self.client()
+            .get(&format!("/v1/apps/{application_id}/models/{model_id}"), None::<&()>)
+            .await
+    }
+}
+
 impl Client {
     pub async fn read_model(&self, model_id: Uuid) -> Result<Model> {
-        let application_id = self.application_id()?;
-        self.get(&format!("/v1/apps/{application_id}/models/{model_id}"), None::<&()>).await
+        self.default_app()?.read_model(model_id).await
     }
 
     pub async fn read_marketplace_model(&self, model_id: Uuid) -> Result<Model> {
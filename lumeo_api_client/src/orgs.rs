@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use strum::{AsRefStr, Display, EnumString};
 use uuid::Uuid;
 
 use super::Client;
@@ -16,8 +17,159 @@ pub struct Organization {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct OrganizationData {
+    pub name: String,
+}
+
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, AsRefStr, Display, EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Role {
+    Owner,
+    Admin,
+    Member,
+    Viewer,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Member {
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewInvitation {
+    pub email: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 impl Client {
     pub async fn get_orgs(&self) -> Result<Vec<Organization>> {
         self.get("/v1/orgs", None::<&()>).await
     }
+
+    pub async fn create_organization(&self, data: &OrganizationData) -> Result<Organization> {
+        self.post("/v1/orgs", data).await
+    }
+
+    pub async fn read_organization(&self, organization_id: Uuid) -> Result<Organization> {
+        self.get(&format!("/v1/orgs/{organization_id}"), None::<&()>).await
+    }
+
+    pub async fn update_organization(
+        &self,
+        organization_id: Uuid,
+        data: &OrganizationData,
+    ) -> Result<Organization> {
+        self.put(&format!("/v1/orgs/{organization_id}"), data).await
+    }
+
+    pub async fn delete_organization(&self, organization_id: Uuid) -> Result<()> {
+        self.delete(&format!("/v1/orgs/{organization_id}"), None::<&()>).await
+    }
+
+    pub async fn list_members(&self, organization_id: Uuid) -> Result<Vec<Member>> {
+        self.get(&format!("/v1/orgs/{organization_id}/members"), None::<&()>).await
+    }
+
+    pub async fn set_member_role(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+        role: Role,
+    ) -> Result<()> {
+        self.put_text(&format!("/v1/orgs/{organization_id}/members/{user_id}/role"), &role).await
+    }
+
+    pub async fn remove_member(&self, organization_id: Uuid, user_id: Uuid) -> Result<()> {
+        self.delete(&format!("/v1/orgs/{organization_id}/members/{user_id}"), None::<&()>).await
+    }
+
+    /// Invites a user by email. The user becomes a member once they accept the invitation.
+    pub async fn invite_member(
+        &self,
+        organization_id: Uuid,
+        invitation: &NewInvitation,
+    ) -> Result<Invitation> {
+        self.post(&format!("/v1/orgs/{organization_id}/invitations"), invitation).await
+    }
+
+    pub async fn list_invitations(&self, organization_id: Uuid) -> Result<Vec<Invitation>> {
+        self.get(&format!("/v1/orgs/{organization_id}/invitations"), None::<&()>).await
+    }
+
+    pub async fn revoke_invitation(
+        &self,
+        organization_id: Uuid,
+        invitation_id: Uuid,
+    ) -> Result<()> {
+        let path = format!("/v1/orgs/{organization_id}/invitations/{invitation_id}");
+        self.delete(&path, None::<&()>).await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{MockServer, Resource};
+
+    #[tokio::test]
+    async fn manages_members() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client_builder().build().unwrap();
+
+        let org = client
+            .create_organization(&OrganizationData { name: "Acme".to_owned() })
+            .await
+            .unwrap();
+        let user_id = Uuid::new_v4();
+        server.insert(
+            Resource::Members,
+            json!({
+                "id": user_id,
+                "user_id": user_id,
+                "organization_id": org.id,
+                "email": "jane@example.com",
+                "role": "member",
+            }),
+        );
+
+        client.set_member_role(org.id, user_id, Role::Admin).await.unwrap();
+        let members = client.list_members(org.id).await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].role, Role::Admin);
+
+        let invitation = client
+            .invite_member(
+                org.id,
+                &NewInvitation { email: "john@example.com".to_owned(), role: Role::Viewer },
+            )
+            .await
+            .unwrap();
+        assert_eq!(invitation.organization_id, org.id);
+        client.revoke_invitation(org.id, invitation.id).await.unwrap();
+        assert!(client.list_invitations(org.id).await.unwrap().is_empty());
+
+        client.remove_member(org.id, user_id).await.unwrap();
+        assert!(client.list_members(org.id).await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,212 @@
+//! Lazy iteration over list endpoints returning more records than fit in a single page.
+//!
+//! List endpoints return records newest first and accept a `limit` as well as a
+//! `created_ts_until` filter (exclusive upper bound of the creation time). Pages are fetched one
+//! after another, using the creation time of the oldest record seen so far as the cursor for the
+//! next page.
+//!
+//! Streams of records that have an update time can also be restricted to the records updated
+//! since a given time, see [`AppClient::get_deployment_updates_stream`]. The highest update time
+//! returned is the `updated_ts_since` cursor of the next call, to only get later updates.
+//!
+//! [`AppClient::get_deployment_updates_stream`]: crate::AppClient::get_deployment_updates_stream
+
+use std::{collections::HashSet, future::Future};
+
+use chrono::{DateTime, Duration, Utc};
+use futures::{stream, Stream, TryStreamExt};
+use uuid::Uuid;
+
+use crate::{error::Error, Result};
+
+/// Page size used when the list parameters don't specify a (positive) limit.
+pub const DEFAULT_PAGE_SIZE: i16 = 100;
+
+/// A record that can be paginated over by its creation time.
+pub(crate) trait Paginated {
+    fn id(&self) -> Uuid;
+    fn created_at(&self) -> DateTime<Utc>;
+}
+
+struct Cursor {
+    created_ts_until: Option<DateTime<Utc>>,
+    limit: i16,
+    // Records created exactly at `created_ts_until - 1µs`, which the next page will contain again.
+    seen: HashSet<Uuid>,
+    done: bool,
+}
+
+/// Returns a stream of all records, fetching pages of `page_size` records lazily.
+///
+/// `fetch_page` is called with the `created_ts_until` filter and the limit of the page to fetch.
+/// The limit is only raised above `page_size` to get past more than `page_size` records sharing a
+/// creation time, and the stream fails with [`Error::PaginationStalled`] if it can't be raised
+/// further.
+pub(crate) fn paginate<'a, T, F, Fut>(
+    created_ts_until: Option<DateTime<Utc>>,
+    page_size: i16,
+    mut fetch_page: F,
+) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: Paginated + 'a,
+    F: FnMut(Option<DateTime<Utc>>, i16) -> Fut + 'a,
+    Fut: Future<Output = Result<Vec<T>>> + 'a,
+{
+    let page_size = if page_size > 0 { page_size } else { DEFAULT_PAGE_SIZE };
+    let cursor = Cursor { created_ts_until, limit: page_size, seen: HashSet::new(), done: false };
+
+    stream::try_unfold(cursor, move |mut cursor| {
+        let limit = cursor.limit;
+        let page = (!cursor.done).then(|| fetch_page(cursor.created_ts_until, limit));
+
+        async move {
+            let page = match page {
+                Some(page) => page.await?,
+                None => return Ok(None),
+            };
+
+            let full = page.len() >= limit as usize;
+            let page: Vec<T> =
+                page.into_iter().filter(|record| !cursor.seen.contains(&record.id())).collect();
+
+            let oldest = match page.iter().map(Paginated::created_at).min() {
+                Some(oldest) => oldest,
+                None if !full => return Ok(None),
+                // A full page only contained records we have already returned, i.e. more than
+                // `limit` records share the same creation time. Fetch it again with a larger limit.
+                None if limit < i16::MAX => {
+                    cursor.limit = limit.saturating_mul(2);
+                    return Ok(Some((stream::iter(Vec::new().into_iter().map(Ok)), cursor)));
+                }
+                None => return Err(Error::PaginationStalled { limit }),
+            };
+            cursor.done = !full;
+            cursor.limit = page_size;
+
+            // The cursor is inclusive (records created at `oldest` may not all fit in this page),
+            // so remember what we've returned at that exact time to skip it in the next page.
+            if cursor.created_ts_until != Some(oldest + Duration::microseconds(1)) {
+                cursor.seen.clear();
+            }
+            cursor.seen.extend(
+                page.iter().filter(|record| record.created_at() == oldest).map(Paginated::id),
+            );
+            cursor.created_ts_until = Some(oldest + Duration::microseconds(1));
+
+            Ok(Some((stream::iter(page.into_iter().map(Ok)), cursor)))
+        }
+    })
+    .try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Reverse,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::TimeZone;
+    use futures::{future, TryStreamExt};
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Record {
+        id: Uuid,
+        created_at: DateTime<Utc>,
+    }
+
+    impl Paginated for Record {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+
+        fn created_at(&self) -> DateTime<Utc> {
+            self.created_at
+        }
+    }
+
+    /// Records sorted newest first, with groups of records sharing a creation time.
+    fn records(count: u32) -> Vec<Record> {
+        let start = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let mut records: Vec<_> = (0..count)
+            .map(|i| Record {
+                id: Uuid::from_u128(u128::from(i)),
+                created_at: start + Duration::seconds(i64::from(i / 3)),
+            })
+            .collect();
+        records.sort_by_key(|record| Reverse(record.created_at));
+        records
+    }
+
+    async fn collect(all: Vec<Record>, page_size: i16) -> (Vec<Record>, usize) {
+        let requests = Arc::new(Mutex::new(0));
+        let stream = paginate(None, page_size, |until, limit| {
+            *requests.lock().unwrap() += 1;
+            let page: Vec<_> = all
+                .iter()
+                .filter(|record| until.map_or(true, |until| record.created_at < until))
+                .take(limit as usize)
+                .cloned()
+                .collect();
+            async move { Ok(page) }
+        });
+
+        let records = stream.try_collect().await.unwrap();
+        let requests = *requests.lock().unwrap();
+        (records, requests)
+    }
+
+    #[tokio::test]
+    async fn returns_every_record_once() {
+        let all = records(250);
+        let (collected, requests) = collect(all.clone(), 100).await;
+
+        assert_eq!(collected, all);
+        assert_eq!(requests, 3);
+    }
+
+    #[tokio::test]
+    async fn handles_page_boundary_inside_timestamp_group() {
+        let all = records(10);
+        let (collected, _) = collect(all.clone(), 4).await;
+
+        assert_eq!(collected, all);
+    }
+
+    #[tokio::test]
+    async fn raises_limit_for_large_timestamp_group() {
+        // Newer than the records of `records()`.
+        let created_at = Utc.with_ymd_and_hms(2022, 2, 1, 0, 0, 0).unwrap();
+        let mut all: Vec<_> =
+            (100..110).map(|i| Record { id: Uuid::from_u128(i), created_at }).collect();
+        all.extend(records(6));
+
+        let (collected, _) = collect(all.clone(), 4).await;
+
+        assert_eq!(collected.len(), all.len());
+        assert!(all.iter().all(|record| collected.contains(record)));
+    }
+
+    #[tokio::test]
+    async fn fails_if_limit_cant_be_raised() {
+        let created_at = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let all: Vec<_> =
+            (0..=i16::MAX as u128).map(|i| Record { id: Uuid::from_u128(i), created_at }).collect();
+
+        let stream =
+            paginate(None, i16::MAX, |_, limit| future::ready(Ok(all[..limit as usize].to_vec())));
+        let err = stream.try_collect::<Vec<_>>().await.unwrap_err();
+
+        assert!(matches!(err, Error::PaginationStalled { limit: i16::MAX }));
+    }
+
+    #[tokio::test]
+    async fn stops_on_empty_page() {
+        let (collected, requests) = collect(vec![], 100).await;
+
+        assert!(collected.is_empty());
+        assert_eq!(requests, 1);
+    }
+}
@@ -5,11 +5,15 @@ use serde::{
     ser::{Serialize, SerializeSeq, Serializer},
 };
 
+pub mod configuration;
+pub mod diff;
 pub mod node;
 pub mod node_properties;
 pub mod pad;
 pub mod resolution;
 
+pub use configuration::*;
+pub use diff::*;
 pub use node::*;
 pub use node_properties::*;
 pub use pad::*;
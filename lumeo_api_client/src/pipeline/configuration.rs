@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{NodeProperties, Pipeline};
+
+/// Property overrides per node id, e.g. a deployment's configuration.
+///
+/// Properties use their serialized names, see [`Pipeline::configure`].
+#[derive(Default, PartialEq, Debug, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Configuration {
+    nodes: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Configuration {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Overrides `property` of node `node_id`. A `null` value unsets optional properties.
+    pub fn set(&mut self, node_id: &str, property: &str, value: impl Into<Value>) {
+        self.nodes.entry(node_id.into()).or_default().insert(property.into(), value.into());
+    }
+
+    pub fn node_by_id(&self, id: &str) -> Option<&Map<String, Value>> {
+        self.nodes.get(id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl From<BTreeMap<String, Map<String, Value>>> for Configuration {
+    fn from(nodes: BTreeMap<String, Map<String, Value>>) -> Self {
+        Self { nodes }
+    }
+}
+
+impl From<Configuration> for BTreeMap<String, Map<String, Value>> {
+    fn from(configuration: Configuration) -> Self {
+        configuration.nodes
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigurationError {
+    #[error("Node `{0}` not found")]
+    UnknownNode(String),
+    #[error("Node `{node}` has no property `{property}`")]
+    UnknownProperty { node: String, property: String },
+    #[error("Type of node `{node}` can't be changed")]
+    TypeChanged { node: String },
+    #[error("Invalid properties of node `{node}`: {source}")]
+    InvalidProperties { node: String, source: serde_json::Error },
+}
+
+impl Pipeline {
+    /// Returns a copy of the pipeline with the properties of its nodes overridden by
+    /// `configuration`.
+    ///
+    /// Each node of the configuration must exist in the pipeline, and the overridden properties
+    /// must be valid for the type of the node. Aliases of property names, e.g. `fps`, aren't
+    /// recognized.
+    pub fn configure(&self, configuration: &Configuration) -> Result<Pipeline, ConfigurationError> {
+        let mut pipeline = self.clone();
+        for (node_id, overrides) in &configuration.nodes {
+            let node = pipeline
+                .nodes
+                .get_mut(node_id)
+                .ok_or_else(|| ConfigurationError::UnknownNode(node_id.clone()))?;
+            let properties = configure_node(node_id, node.properties(), overrides)?;
+            *node.properties_mut() = properties;
+        }
+
+        Ok(pipeline)
+    }
+}
+
+fn configure_node(
+    node_id: &str,
+    properties: &NodeProperties,
+    overrides: &Map<String, Value>,
+) -> Result<NodeProperties, ConfigurationError> {
+    let invalid =
+        |source| ConfigurationError::InvalidProperties { node: node_id.to_owned(), source };
+
+    let mut merged = to_map(properties);
+    let node_type = merged.get("type").cloned();
+    merged.extend(overrides.clone());
+    if merged.get("type") != node_type.as_ref() {
+        return Err(ConfigurationError::TypeChanged { node: node_id.to_owned() });
+    }
+
+    let configured: NodeProperties =
+        serde_json::from_value(Value::Object(merged.clone())).map_err(invalid)?;
+
+    // Unknown properties are ignored when deserializing, so they are missing once serialized.
+    let known = to_map(&configured);
+    let is_known = |name: &String| {
+        // Unset properties are missing as well, set them to a value to tell them apart. A known
+        // property then either is serialized or can't be deserialized from that value.
+        known.contains_key(name) || {
+            let mut probe = merged.clone();
+            probe.insert(name.clone(), Value::Bool(true));
+            serde_json::from_value::<NodeProperties>(Value::Object(probe))
+                .map_or(true, |probed| to_map(&probed).contains_key(name))
+        }
+    };
+    if let Some(property) = overrides.keys().find(|name| !is_known(name)) {
+        return Err(ConfigurationError::UnknownProperty {
+            node: node_id.to_owned(),
+            property: property.clone(),
+        });
+    }
+
+    Ok(configured)
+}
+
+pub(super) fn to_map(properties: &NodeProperties) -> Map<String, Value> {
+    match serde_json::to_value(properties).expect("Failed to serialize JSON") {
+        Value::Object(map) => map,
+        _ => unreachable!("node properties serialize to an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::pipeline::EncodeProperties;
+
+    fn pipeline() -> Pipeline {
+        serde_json::from_value(json!([
+            {
+                "id": "encode1",
+                "properties": { "type": "encode", "codec": "h264", "quality": 10, "fps": 15 },
+                "wires": { "output": ["stream_rtsp_out1.input"] }
+            },
+            {
+                "id": "stream_rtsp_out1",
+                "properties": { "type": "stream_rtsp_out" },
+                "wires": {}
+            }
+        ]))
+        .unwrap()
+    }
+
+    fn encode_properties(pipeline: &Pipeline) -> &EncodeProperties {
+        match pipeline.node_by_id("encode1").unwrap().properties() {
+            NodeProperties::Encode(properties) => properties,
+            properties => panic!("unexpected properties {properties:?}"),
+        }
+    }
+
+    #[test]
+    fn merges_overrides() {
+        let pipeline = pipeline();
+        let mut configuration = Configuration::new();
+        configuration.set("encode1", "max_bitrate", 1_500_000);
+        configuration.set("encode1", "quality", Value::Null);
+        configuration.set("encode1", "bitrate", Value::Null);
+
+        let configured = pipeline.configure(&configuration).unwrap();
+
+        let properties = encode_properties(&configured);
+        assert_eq!(properties.max_bitrate, Some(1_500_000));
+        assert_eq!(properties.quality, None);
+        assert_eq!(properties.framerate, Some(15));
+        assert_eq!(
+            configured.node_by_id("stream_rtsp_out1"),
+            pipeline.node_by_id("stream_rtsp_out1")
+        );
+        assert_eq!(encode_properties(&pipeline).quality, Some(10));
+    }
+
+    #[test]
+    fn rejects_invalid_overrides() {
+        let pipeline = pipeline();
+        let configure = |node_id, property, value| {
+            let mut configuration = Configuration::new();
+            configuration.set(node_id, property, value);
+            pipeline.configure(&configuration).unwrap_err()
+        };
+
+        let err = configure("encode2", "quality", json!(5));
+        assert!(matches!(err, ConfigurationError::UnknownNode(node) if node == "encode2"));
+
+        let err = configure("encode1", "qualty", json!(5));
+        assert!(
+            matches!(err, ConfigurationError::UnknownProperty { property, .. } if property == "qualty")
+        );
+
+        let err = configure("encode1", "qualty", Value::Null);
+        assert!(
+            matches!(err, ConfigurationError::UnknownProperty { property, .. } if property == "qualty")
+        );
+
+        let err = configure("encode1", "quality", json!("high"));
+        assert!(
+            matches!(err, ConfigurationError::InvalidProperties { node, .. } if node == "encode1")
+        );
+
+        let err = configure("encode1", "type", json!("grid"));
+        assert!(matches!(err, ConfigurationError::TypeChanged { .. }));
+    }
+
+    #[test]
+    fn deserializes_deployment_configuration() {
+        let configuration: Configuration =
+            serde_json::from_value(json!({ "encode1": { "codec": "h265" } })).unwrap();
+
+        let configured = pipeline().configure(&configuration).unwrap();
+
+        assert_eq!(encode_properties(&configured).codec, "h265");
+    }
+}
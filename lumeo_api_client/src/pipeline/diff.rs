@@ -0,0 +1,316 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use super::{configuration::to_map, Configuration, Node, Pipeline, SinkPad};
+
+/// Properties a running pipeline can't pick up: the source of video nodes, including their
+/// runtime (`rtsp`, `usb`, ...) and the URI of outputs, and the codec of encoders.
+const RESTART_PROPERTIES: &[&str] = &[
+    "source_type",
+    "source_id",
+    "usb",
+    "csi",
+    "url_file",
+    "lumeo_file",
+    "rtsp",
+    "web_rtc",
+    "uri",
+    "codec",
+];
+
+/// Structural differences between two versions of a pipeline, see [`Pipeline::diff`].
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct PipelineDiff {
+    /// Ids of nodes only in the new pipeline
+    pub added: Vec<String>,
+    /// Ids of nodes only in the old pipeline
+    pub removed: Vec<String>,
+    /// Nodes in both pipelines that differ
+    pub changed: Vec<NodeDiff>,
+}
+
+impl PipelineDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether nodes were added, removed, changed type or rewired.
+    pub fn is_structural(&self) -> bool {
+        !self.added.is_empty()
+            || !self.removed.is_empty()
+            || self.changed.iter().any(|node| node.type_changed || !node.rewired.is_empty())
+    }
+
+    /// Whether the changes can't be applied to a running deployment, see
+    /// [`Pipeline::plan_update`].
+    pub fn requires_restart(&self) -> bool {
+        self.is_structural()
+            || self
+                .changed
+                .iter()
+                .flat_map(|node| &node.properties)
+                .any(PropertyChange::requires_restart)
+    }
+}
+
+impl PropertyChange {
+    /// Whether the property changes the source or the codec of a node.
+    pub fn requires_restart(&self) -> bool {
+        RESTART_PROPERTIES.contains(&self.name.as_str())
+    }
+}
+
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct NodeDiff {
+    pub id: String,
+    pub type_changed: bool,
+    /// Changed properties, by serialized name
+    pub properties: Vec<PropertyChange>,
+    /// Source pads linked to different sink pads
+    pub rewired: Vec<PadChange>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PropertyChange {
+    pub name: String,
+    /// `None` if the property wasn't set
+    pub old: Option<Value>,
+    /// `None` if the property is unset
+    pub new: Option<Value>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PadChange {
+    pub name: String,
+    pub old: Vec<SinkPad>,
+    pub new: Vec<SinkPad>,
+}
+
+/// How to bring a running deployment from one pipeline to another, see [`Pipeline::plan_update`].
+#[derive(PartialEq, Debug, Clone)]
+pub enum UpdatePlan {
+    Unchanged,
+    /// Only properties changed, the deployment can be updated with this configuration.
+    Reconfigure(Configuration),
+    /// The structure of the pipeline or the source or codec of a node changed, the deployment
+    /// must be restarted with the new pipeline (see [`RestartDeployment`](crate::commands::deployment::RestartDeployment)).
+    Restart,
+}
+
+impl Pipeline {
+    /// Compares the pipeline with its new version `new`.
+    pub fn diff(&self, new: &Pipeline) -> PipelineDiff {
+        let mut diff = PipelineDiff::default();
+        for node in self.nodes() {
+            match new.node_by_id(node.id()) {
+                Some(new_node) => {
+                    if let Some(node_diff) = diff_node(node, new_node) {
+                        diff.changed.push(node_diff);
+                    }
+                }
+                None => diff.removed.push(node.id().to_owned()),
+            }
+        }
+        diff.added = new
+            .nodes()
+            .filter(|node| self.node_by_id(node.id()).is_none())
+            .map(|node| node.id().to_owned())
+            .collect();
+
+        diff
+    }
+
+    /// Decides how a deployment running this pipeline can be updated to `new`.
+    ///
+    /// Property changes are applied as configuration, except for changes of sources (e.g. the
+    /// URI of a stream) and codecs, which require a restart like structural changes.
+    pub fn plan_update(&self, new: &Pipeline) -> UpdatePlan {
+        let diff = self.diff(new);
+        if diff.is_empty() {
+            return UpdatePlan::Unchanged;
+        }
+        if diff.requires_restart() {
+            return UpdatePlan::Restart;
+        }
+
+        let mut configuration = Configuration::new();
+        for node in &diff.changed {
+            for property in &node.properties {
+                let value = property.new.clone().unwrap_or(Value::Null);
+                configuration.set(&node.id, &property.name, value);
+            }
+        }
+        UpdatePlan::Reconfigure(configuration)
+    }
+}
+
+fn diff_node(old: &Node, new: &Node) -> Option<NodeDiff> {
+    let old_properties = to_map(old.properties());
+    let new_properties = to_map(new.properties());
+    let names: BTreeSet<&String> = old_properties.keys().chain(new_properties.keys()).collect();
+    let properties = names
+        .into_iter()
+        .filter(|name| *name != "type" && old_properties.get(*name) != new_properties.get(*name))
+        .map(|name| PropertyChange {
+            name: name.clone(),
+            old: old_properties.get(name).cloned(),
+            new: new_properties.get(name).cloned(),
+        })
+        .collect();
+
+    let pads: BTreeSet<&str> = old
+        .source_pads()
+        .all()
+        .into_iter()
+        .chain(new.source_pads().all())
+        .map(|pad| pad.name.as_str())
+        .collect();
+    let sinks = |node: &Node, pad| node.source_pads().get(pad).map(|p| p.sinks.clone());
+    let rewired = pads
+        .into_iter()
+        .filter_map(|name| {
+            let old = sinks(old, name).unwrap_or_default();
+            let new = sinks(new, name).unwrap_or_default();
+            (old != new).then(|| PadChange { name: name.to_owned(), old, new })
+        })
+        .collect();
+
+    let diff = NodeDiff {
+        id: old.id().to_owned(),
+        type_changed: old_properties.get("type") != new_properties.get("type"),
+        properties,
+        rewired,
+    };
+    let unchanged = !diff.type_changed && diff.properties.is_empty() && diff.rewired.is_empty();
+    (!unchanged).then(|| diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::pipeline::NodeProperties;
+
+    fn pipeline(encode: Value, encode_wires: Value) -> Pipeline {
+        serde_json::from_value(json!([
+            { "id": "encode1", "properties": encode, "wires": encode_wires },
+            { "id": "stream_rtsp_out1", "properties": { "type": "stream_rtsp_out" }, "wires": {} },
+            { "id": "stream_rtsp_out2", "properties": { "type": "stream_rtsp_out" }, "wires": {} }
+        ]))
+        .unwrap()
+    }
+
+    fn encode(quality: Option<u32>) -> Value {
+        json!({ "type": "encode", "codec": "h264", "quality": quality })
+    }
+
+    fn wires(sink: &str) -> Value {
+        json!({ "output": [sink] })
+    }
+
+    #[test]
+    fn unchanged() {
+        let old = pipeline(encode(Some(10)), wires("stream_rtsp_out1.input"));
+
+        assert!(old.diff(&old.clone()).is_empty());
+        assert_eq!(old.plan_update(&old), UpdatePlan::Unchanged);
+    }
+
+    #[test]
+    fn property_changes_reconfigure() {
+        let old = pipeline(encode(Some(10)), wires("stream_rtsp_out1.input"));
+        let mut new = pipeline(encode(None), wires("stream_rtsp_out1.input"));
+        if let Some(node) = new.nodes_mut().find(|node| node.id() == "encode1") {
+            if let NodeProperties::Encode(properties) = node.properties_mut() {
+                properties.max_bitrate = Some(1_000_000);
+            }
+        }
+
+        let diff = old.diff(&new);
+        assert!(!diff.is_structural());
+        assert_eq!(diff.changed.len(), 1);
+        let changes: Vec<_> = diff.changed[0]
+            .properties
+            .iter()
+            .map(|p| (p.name.as_str(), p.old.clone(), p.new.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            [("max_bitrate", None, Some(json!(1_000_000))), ("quality", Some(json!(10)), None)]
+        );
+
+        let configuration = match old.plan_update(&new) {
+            UpdatePlan::Reconfigure(configuration) => configuration,
+            plan => panic!("unexpected plan {plan:?}"),
+        };
+        assert_eq!(old.configure(&configuration).unwrap(), new);
+    }
+
+    #[test]
+    fn structural_changes_restart() {
+        let old = pipeline(encode(Some(10)), wires("stream_rtsp_out1.input"));
+
+        let rewired = pipeline(encode(Some(10)), wires("stream_rtsp_out2.input"));
+        let diff = old.diff(&rewired);
+        assert_eq!(
+            diff.changed[0].rewired,
+            [PadChange {
+                name: "output".into(),
+                old: vec!["stream_rtsp_out1.input".parse().unwrap()],
+                new: vec!["stream_rtsp_out2.input".parse().unwrap()],
+            }]
+        );
+        assert_eq!(old.plan_update(&rewired), UpdatePlan::Restart);
+
+        let mut added = old.clone();
+        added.add_node(Node::new(
+            "encode2",
+            old.node_by_id("encode1").unwrap().properties().clone(),
+            None,
+        ));
+        assert_eq!(old.diff(&added).added, ["encode2"]);
+        assert_eq!(added.diff(&old).removed, ["encode2"]);
+        assert_eq!(old.plan_update(&added), UpdatePlan::Restart);
+
+        let retyped = pipeline(
+            json!({ "type": "grid", "rows": 2, "columns": 2 }),
+            wires("stream_rtsp_out1.input"),
+        );
+        assert!(old.diff(&retyped).changed[0].type_changed);
+        assert_eq!(old.plan_update(&retyped), UpdatePlan::Restart);
+    }
+
+    #[test]
+    fn source_and_codec_changes_restart() {
+        let old = pipeline(encode(Some(10)), wires("stream_rtsp_out1.input"));
+
+        let h265 = pipeline(
+            json!({ "type": "encode", "codec": "h265", "quality": 10 }),
+            wires("stream_rtsp_out1.input"),
+        );
+        let diff = old.diff(&h265);
+        assert!(!diff.is_structural());
+        assert!(diff.requires_restart());
+        assert_eq!(old.plan_update(&h265), UpdatePlan::Restart);
+
+        let source = |uri: &str| -> Pipeline {
+            serde_json::from_value(json!([{
+                "id": "video1",
+                "properties": {
+                    "type": "video",
+                    "source_type": "stream",
+                    "source_id": "00000000-0000-0000-0000-000000000001",
+                    "rtsp": { "uri": uri, "name": "Lobby" },
+                },
+                "wires": {},
+            }]))
+            .unwrap()
+        };
+        let old = source("rtsp://192.168.0.42/stream");
+        let new = source("rtsp://192.168.0.43/stream");
+        assert_eq!(old.diff(&new).changed[0].properties[0].name, "rtsp");
+        assert_eq!(old.plan_update(&new), UpdatePlan::Restart);
+    }
+}
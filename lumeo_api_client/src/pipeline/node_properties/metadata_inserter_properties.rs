@@ -1,12 +1,44 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::lumeo_url::{LumeoUrl, LumeoUrlError};
+
 /// Insert metadata frames from an external source.
 ///
 /// Reads lines (newline delimited) of TEXT from the given URI and appends them to the frames. The
-/// only supported scheme currently is `lumeo`, which will use TCP as transport.
+/// only supported scheme currently is `lumeo`, which will use TCP as transport, see
+/// [`LumeoUrl::Metadata`].
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MetadataInserterProperties {
     #[serde(rename = "source_uri")]
     pub uri: Url,
 }
+
+impl MetadataInserterProperties {
+    /// The source URI as [`LumeoUrl`], failing for URIs of other shapes.
+    pub fn lumeo_url(&self) -> Result<LumeoUrl, LumeoUrlError> {
+        LumeoUrl::try_from(&self.uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keeps_uris_of_other_shapes() {
+        let properties: MetadataInserterProperties =
+            serde_json::from_value(json!({ "source_uri": "lumeo://0.0.0.0:5000" })).unwrap();
+        assert_eq!(
+            properties.lumeo_url(),
+            Ok(LumeoUrl::Metadata { host: "0.0.0.0".into(), port: 5000 })
+        );
+
+        let properties: MetadataInserterProperties =
+            serde_json::from_value(json!({ "source_uri": "tcp://0.0.0.0:5000" })).unwrap();
+        assert_eq!(properties.uri.as_str(), "tcp://0.0.0.0:5000");
+        assert!(properties.lumeo_url().is_err());
+    }
+}
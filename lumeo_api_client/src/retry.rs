@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{header, Method, Response};
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_ELAPSED: Duration = Duration::from_secs(120);
+
+/// Controls how requests failing with a transient error are retried.
+///
+/// Only idempotent requests (`GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS`) are retried, and only when
+/// the request could not be sent or the server answered with `429`, `502`, `503` or `504`.
+/// Delays grow exponentially from `initial_backoff` up to `max_backoff`, with full jitter applied.
+/// A `Retry-After` header sent by the server takes precedence over the computed delay and isn't
+/// bounded by `max_backoff`. No retry is attempted if it would start more than `max_elapsed` after
+/// the first attempt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// Upper bound of the delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the computed delay between any two attempts
+    pub max_backoff: Duration,
+    /// Time after the first attempt past which no retry is started
+    pub max_elapsed: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Default::default() }
+    }
+
+    pub(crate) fn is_retryable_method(method: &Method) -> bool {
+        matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        )
+    }
+
+    /// Returns the delay before retry number `attempt` (starting at 0), `None` if the retry would
+    /// start after `max_elapsed`. `elapsed` is the time since the first attempt.
+    pub(crate) fn delay(
+        &self,
+        attempt: u32,
+        retry_after: Option<Duration>,
+        elapsed: Duration,
+    ) -> Option<Duration> {
+        let delay = retry_after.unwrap_or_else(|| {
+            let ceiling = self
+                .initial_backoff
+                .checked_mul(2_u32.saturating_pow(attempt))
+                .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+
+            rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+        });
+
+        let within_deadline =
+            elapsed.checked_add(delay).map_or(false, |start| start <= self.max_elapsed);
+        within_deadline.then(|| delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            max_elapsed: DEFAULT_MAX_ELAPSED,
+        }
+    }
+}
+
+/// Extracts the `Retry-After` header, which is either a number of seconds or an HTTP date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((date.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn retries_only_idempotent_methods() {
+        assert!(RetryPolicy::is_retryable_method(&Method::GET));
+        assert!(RetryPolicy::is_retryable_method(&Method::PUT));
+        assert!(RetryPolicy::is_retryable_method(&Method::DELETE));
+        assert!(!RetryPolicy::is_retryable_method(&Method::POST));
+        assert!(!RetryPolicy::is_retryable_method(&Method::PATCH));
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            max_elapsed: Duration::from_secs(60),
+        };
+
+        for attempt in 0..10 {
+            let ceiling = Duration::from_millis(100 * 2_u64.pow(attempt)).min(policy.max_backoff);
+            assert!(policy.delay(attempt, None, Duration::ZERO).unwrap() <= ceiling);
+        }
+        assert!(policy.delay(u32::MAX, None, Duration::ZERO).unwrap() <= policy.max_backoff);
+        assert_eq!(policy.delay(0, None, Duration::from_secs(61)), None);
+    }
+
+    #[test]
+    fn retry_after_takes_precedence() {
+        let policy = RetryPolicy::default();
+        let delay = |retry_after, elapsed| {
+            policy.delay(0, Some(Duration::from_secs(retry_after)), Duration::from_secs(elapsed))
+        };
+
+        assert_eq!(delay(2, 0), Some(Duration::from_secs(2)));
+        assert_eq!(delay(60, 0), Some(Duration::from_secs(60)));
+        assert_eq!(delay(60, 90), None);
+        assert_eq!(delay(3600, 0), None);
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap();
+
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -0,0 +1,114 @@
+use uuid::Uuid;
+
+use crate::{error::Error, Client, Result};
+
+/// Client scoped to a single application.
+///
+/// Obtained with [`Client::app`]. Scoped handles are cheap to create, so a single [`Client`] can
+/// serve any number of applications concurrently.
+#[derive(Clone, Copy)]
+pub struct AppClient<'a> {
+    client: &'a Client,
+    application_id: Uuid,
+}
+
+/// Client scoped to a single gateway of an application.
+///
+/// Obtained with [`AppClient::gateway`]. Endpoints that act on behalf of a gateway are only
+/// available on this type.
+#[derive(Clone, Copy)]
+pub struct GatewayClient<'a> {
+    app: AppClient<'a>,
+    gateway_id: Uuid,
+}
+
+impl Client {
+    pub fn app(&self, application_id: Uuid) -> AppClient<'_> {
+        AppClient { client: self, application_id }
+    }
+
+    /// Scopes the client to the application it has been created for.
+    pub(crate) fn default_app(&self) -> Result<AppClient<'_>> {
+        let application_id = self
+            .application_id
+            .ok_or(Error::ApplicationIdMissing)
+            .map_err(|err| self.through_cb(err))?;
+        Ok(self.app(application_id))
+    }
+
+    /// Scopes the client to the gateway it has been created for.
+    pub(crate) fn default_gateway(&self) -> Result<GatewayClient<'_>> {
+        let app = self.default_app()?;
+        let gateway_id =
+            self.gateway_id.ok_or(Error::GatewayIdMissing).map_err(|err| self.through_cb(err))?;
+        Ok(app.gateway(gateway_id))
+    }
+}
+
+impl<'a> AppClient<'a> {
+    pub fn gateway(self, gateway_id: Uuid) -> GatewayClient<'a> {
+        GatewayClient { app: self, gateway_id }
+    }
+
+    pub fn client(self) -> &'a Client {
+        self.client
+    }
+
+    pub fn application_id(self) -> Uuid {
+        self.application_id
+    }
+}
+
+impl<'a> GatewayClient<'a> {
+    pub fn app(self) -> AppClient<'a> {
+        self.app
+    }
+
+    pub fn client(self) -> &'a Client {
+        self.app.client
+    }
+
+    pub fn application_id(self) -> Uuid {
+        self.app.application_id
+    }
+
+    pub fn gateway_id(self) -> Uuid {
+        self.gateway_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn unscoped_client_reports_missing_ids() {
+        let client = Client::new("http://localhost".to_owned(), String::new(), None, None, None)
+            .expect("Failed to create client");
+        assert!(matches!(client.list_cameras(None).await, Err(Error::ApplicationIdMissing)));
+
+        let client = Client::new(
+            "http://localhost".to_owned(),
+            String::new(),
+            Some(Uuid::nil()),
+            None,
+            None,
+        )
+        .expect("Failed to create client");
+        assert!(matches!(client.read_gateway().await, Err(Error::GatewayIdMissing)));
+    }
+
+    #[test]
+    fn scopes_carry_ids() {
+        let client = Client::new("http://localhost".to_owned(), String::new(), None, None, None)
+            .expect("Failed to create client");
+        let application_id = Uuid::from_u128(1);
+        let gateway_id = Uuid::from_u128(2);
+
+        let gateway = client.app(application_id).gateway(gateway_id);
+        assert_eq!(gateway.application_id(), application_id);
+        assert_eq!(gateway.gateway_id(), gateway_id);
+        assert_eq!(gateway.app().application_id(), application_id);
+    }
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::Client;
-use crate::Result;
+use crate::{AppClient, Result};
 
 #[derive(Default, Serialize)]
 pub struct SnapshotParams {
@@ -14,23 +14,63 @@ pub struct SnapshotResponse {
     pub file_id: Uuid,
 }
 
+impl AppClient<'_> {
+    pub async fn take_camera_snapshot(&self, camera_id: Uuid) -> Result<SnapshotResponse> {
+        let application_id = self.application_id();
+        self.client()
+            .post(
+                &format!("/v1/apps/{application_id}/cameras/{camera_id}/snapshot"),
+                &SnapshotParams::default(),
+            )
+            .await
+    }
+
+    pub async fn take_stream_snapshot(&self, stream_id: Uuid) -> Result<SnapshotResponse> {
+        let application_id = self.application_id();
+        self.client()
+            .post(
+                &format!("/v1/apps/{application_id}/streams/{stream_id}/snapshot"),
+                &SnapshotParams::default(),
+            )
+            .await
+    }
+
+    pub async fn set_camera_snapshot_file_id(
+        &self,
+        camera_id: Uuid,
+        snapshot_file_id: Uuid,
+    ) -> Result<()> {
+        let application_id = self.application_id();
+        self.client()
+            .put_text(
+                &format!("/v1/apps/{application_id}/cameras/{camera_id}/snapshot_file_id"),
+                &snapshot_file_id.hyphenated(),
+            )
+            .await
+    }
+
+    pub async fn set_stream_snapshot_file_id(
+        &self,
+        stream_id: Uuid,
+        snapshot_file_id: Uuid,
+    ) -> Result<()> {
+        let application_id = self.application_id();
+        self.client()
+            .put_text(
+                &format!("/v1/apps/{application_id}/streams/{stream_id}/snapshot_file_id"),
+                &snapshot_file_id.hyphenated(),
+            )
+            .await
+    }
+}
+
 impl Client {
     pub async fn take_camera_snapshot(&self, camera_id: Uuid) -> Result<SnapshotResponse> {
-        let application_id = self.application_id()?;
-        self.post(
-            &format!("/v1/apps/{application_id}/cameras/{camera_id}/snapshot"),
-            &SnapshotParams::default(),
-        )
-        .await
+        self.default_app()?.take_camera_snapshot(camera_id).await
     }
 
     pub async fn take_stream_snapshot(&self, stream_id: Uuid) -> Result<SnapshotResponse> {
-        let application_id = self.application_id()?;
-        self.post(
-            &format!("/v1/apps/{application_id}/streams/{stream_id}/snapshot"),
-            &SnapshotParams::default(),
-        )
-        .await
+        self.default_app()?.take_stream_snapshot(stream_id).await
     }
 
     pub async fn set_camera_snapshot_file_id(
@@ -38,12 +78,7 @@ impl Client {
         camera_id: Uuid,
         snapshot_file_id: Uuid,
     ) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.put_text(
-            &format!("/v1/apps/{application_id}/cameras/{camera_id}/snapshot_file_id"),
-            &snapshot_file_id.hyphenated(),
-        )
-        .await
+        self.default_app()?.set_camera_snapshot_file_id(camera_id, snapshot_file_id).await
     }
 
     pub async fn set_stream_snapshot_file_id(
@@ -51,11 +86,6 @@ impl Client {
         stream_id: Uuid,
         snapshot_file_id: Uuid,
     ) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.put_text(
-            &format!("/v1/apps/{application_id}/streams/{stream_id}/snapshot_file_id"),
-            &snapshot_file_id.hyphenated(),
-        )
-        .await
+        self.default_app()?.set_stream_snapshot_file_id(stream_id, snapshot_file_id).await
     }
 }
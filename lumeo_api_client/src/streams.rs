@@ -5,9 +5,9 @@ use url::Url;
 use uuid::Uuid;
 
 use super::Client;
-use crate::Result;
+use crate::{AppClient, Result};
 
-#[derive(Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct StreamData {
     pub name: Option<String>,
     pub source: StreamSource,
@@ -73,24 +73,46 @@ pub enum StreamStatus {
     Unknown,
 }
 
+impl AppClient<'_> {
+    pub async fn create_stream(&self, stream: &StreamData) -> Result<Stream> {
+        let application_id = self.application_id();
+        self.client().post(&format!("/v1/apps/{application_id}/streams"), stream).await
+    }
+
+    pub async fn read_stream(&self, stream_id: Uuid) -> Result<Stream> {
+        let application_id = self.application_id();
+        self.client()
+            .get(&format!("/v1/apps/{application_id}/streams/{stream_id}"), None::<&()>)
+            .await
+    }
+
+    pub async fn update_stream(&self, stream_id: Uuid, stream: &StreamData) -> Result<Stream> {
+        let application_id = self.application_id();
+        self.client().put(&format!("/v1/apps/{application_id}/streams/{stream_id}"), stream).await
+    }
+
+    pub async fn delete_stream(&self, stream_id: Uuid) -> Result<()> {
+        let application_id = self.application_id();
+        self.client()
+            .delete(&format!("/v1/apps/{application_id}/streams/{stream_id}"), None::<&()>)
+            .await
+    }
+}
+
 impl Client {
     pub async fn create_stream(&self, stream: &StreamData) -> Result<Stream> {
-        let application_id = self.application_id()?;
-        self.post(&format!("/v1/apps/{application_id}/streams"), stream).await
+        self.default_app()?.create_stream(stream).await
     }
 
     pub async fn read_stream(&self, stream_id: Uuid) -> Result<Stream> {
-        let application_id = self.application_id()?;
-        self.get(&format!("/v1/apps/{application_id}/streams/{stream_id}"), None::<&()>).await
+        self.default_app()?.read_stream(stream_id).await
     }
 
     pub async fn update_stream(&self, stream_id: Uuid, stream: &StreamData) -> Result<Stream> {
-        let application_id = self.application_id()?;
-        self.put(&format!("/v1/apps/{application_id}/streams/{stream_id}"), stream).await
+        self.default_app()?.update_stream(stream_id, stream).await
     }
 
     pub async fn delete_stream(&self, stream_id: Uuid) -> Result<()> {
-        let application_id = self.application_id()?;
-        self.delete(&format!("/v1/apps/{application_id}/streams/{stream_id}"), None::<&()>).await
+        self.default_app()?.delete_stream(stream_id).await
     }
 }
@@ -0,0 +1,520 @@
+//! In-process mock of the Lumeo API server, for testing code that uses [`Client`].
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use lumeo_api_client::testing::{ErrorInjection, MockServer, Resource};
+//! use reqwest::StatusCode;
+//! use uuid::Uuid;
+//!
+//! let server = MockServer::start().await?;
+//! let application_id = Uuid::new_v4();
+//! let client = server.client_builder().application_id(application_id).build()?;
+//!
+//! server.inject_error(ErrorInjection::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable").times(1));
+//! let cameras = client.list_cameras(None).await?;
+//!
+//! assert!(cameras.is_empty());
+//! assert_eq!(server.requests().len(), 2);
+//! assert!(server.records(Resource::Cameras).is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use chrono::Utc;
+use hyper::{
+    body::Bytes,
+    service::{make_service_fn, service_fn},
+    Server,
+};
+use reqwest::{header::HeaderMap, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::{error::ApiServerResponse, Client, ClientBuilder};
+
+mod routes;
+
+/// Token accepted by the server unless changed with [`MockServer::set_token`].
+pub const DEFAULT_TOKEN: &str = "mock-token";
+
+/// Kinds of records stored by the mock server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resource {
+    Applications,
+    Cameras,
+    Deployments,
+    DiscoveryRequests,
+    Events,
+    Files,
+    Gateways,
+    Invitations,
+    /// Organization members, stored with their `user_id` as `id`
+    Members,
+    Models,
+    Organizations,
+    Streams,
+    VideoSourceMetrics,
+}
+
+impl Resource {
+    /// Name used in the context of `resource-not-found` errors.
+    fn name(self) -> &'static str {
+        match self {
+            Resource::Applications => "application",
+            Resource::Cameras => "camera",
+            Resource::Deployments => "deployment",
+            Resource::DiscoveryRequests => "discovery_request",
+            Resource::Events => "event",
+            Resource::Files => "file",
+            Resource::Gateways => "gateway",
+            Resource::Invitations => "invitation",
+            Resource::Members => "member",
+            Resource::Models => "model",
+            Resource::Organizations => "organization",
+            Resource::Streams => "stream",
+            Resource::VideoSourceMetrics => "video_source_metric",
+        }
+    }
+}
+
+/// An error the server answers matching requests with instead of handling them.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct ErrorInjection {
+    method: Option<Method>,
+    path_prefix: String,
+    status: StatusCode,
+    response: ApiServerResponse,
+    retry_after: Option<u64>,
+    remaining: Option<usize>,
+}
+
+impl ErrorInjection {
+    /// Fails every request with `status` and error `code`.
+    pub fn new(status: StatusCode, code: &str) -> Self {
+        Self {
+            method: None,
+            path_prefix: String::new(),
+            status,
+            response: ApiServerResponse {
+                code: code.to_owned(),
+                message: format!("Injected `{code}` error"),
+                context: None,
+            },
+            retry_after: None,
+            remaining: None,
+        }
+    }
+
+    /// Only fails requests with this method.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Only fails requests whose path starts with `path_prefix`.
+    pub fn path(mut self, path_prefix: &str) -> Self {
+        self.path_prefix = path_prefix.to_owned();
+        self
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.response.message = message.to_owned();
+        self
+    }
+
+    pub fn context(mut self, context: JsonValue) -> Self {
+        self.response.context = Some(context);
+        self
+    }
+
+    /// Sends a `Retry-After` header with the error.
+    pub fn retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    /// Only fails the next `times` matching requests.
+    pub fn times(mut self, times: usize) -> Self {
+        self.remaining = Some(times);
+        self
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().map_or(true, |m| m == method)
+            && path.starts_with(&self.path_prefix)
+            && self.remaining != Some(0)
+    }
+}
+
+/// A request received by the mock server.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    url: String,
+    token: Option<String>,
+    records: HashMap<Resource, BTreeMap<Uuid, JsonValue>>,
+    /// Uploaded file content by storage path
+    contents: HashMap<String, Bytes>,
+    injections: Vec<ErrorInjection>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Mock Lumeo API server bound to a local port.
+///
+/// Records are stored as JSON and created, read, updated and deleted by the routes the client
+/// calls. The server shuts down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port. Must be called within a Tokio runtime.
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let state = Arc::new(Mutex::new(State {
+            url: format!("http://{addr}"),
+            token: Some(DEFAULT_TOKEN.to_owned()),
+            ..Default::default()
+        }));
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    routes::handle(state.clone(), request)
+                }))
+            }
+        });
+
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(server);
+
+        Ok(Self { addr, state, shutdown: Some(shutdown) })
+    }
+
+    /// Base URL to create clients with.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Builder for a client talking to this server, authenticated with [`DEFAULT_TOKEN`].
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder(self.url(), DEFAULT_TOKEN)
+    }
+
+    /// Sets the token the server accepts, or accepts any token if `None`.
+    ///
+    /// Logging in returns the current token.
+    pub fn set_token(&self, token: Option<&str>) {
+        self.state().token = token.map(ToOwned::to_owned);
+    }
+
+    pub fn inject_error(&self, injection: ErrorInjection) {
+        self.state().injections.push(injection);
+    }
+
+    pub fn clear_errors(&self) {
+        self.state().injections.clear();
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state().requests.clone()
+    }
+
+    pub fn clear_requests(&self) {
+        self.state().requests.clear();
+    }
+
+    /// Stores a record, filling `id`, `created_at` and `updated_at` if they are missing.
+    ///
+    /// Returns the record's id.
+    pub fn insert(&self, resource: Resource, mut record: JsonValue) -> Uuid {
+        let now = json!(Utc::now());
+        let object = record.as_object_mut().expect("Record must be a JSON object");
+        let id = match object.get("id").and_then(JsonValue::as_str).map(Uuid::parse_str) {
+            Some(Ok(id)) => id,
+            _ => Uuid::new_v4(),
+        };
+        object.insert("id".to_owned(), json!(id));
+        object.entry("created_at").or_insert_with(|| now.clone());
+        object.entry("updated_at").or_insert(now);
+
+        self.state().records.entry(resource).or_default().insert(id, record);
+        id
+    }
+
+    pub fn record(&self, resource: Resource, id: Uuid) -> Option<JsonValue> {
+        self.state().records.get(&resource)?.get(&id).cloned()
+    }
+
+    pub fn records(&self, resource: Resource) -> Vec<JsonValue> {
+        self.state().records.get(&resource).map_or_else(Vec::new, |r| r.values().cloned().collect())
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chrono::DateTime;
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{
+        auth::{LoginCredentials, LoginParams},
+        error::{ApiError, Error, ResourceNotFound},
+        files::{FileCloudStatus, FileData},
+        streams::{StreamData, StreamSource, StreamStatus, StreamType},
+        RetryPolicy,
+    };
+
+    fn file_data(name: &str) -> FileData {
+        FileData {
+            name: name.to_owned(),
+            size: 42,
+            duration: None,
+            cloud_status: FileCloudStatus::Disabled,
+            gateway_id: None,
+            local_path: Some(format!("/var/clips/{name}")),
+            pipeline_id: None,
+            node_id: None,
+            deployment_id: None,
+            camera_id: None,
+            stream_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+
+        let file = client.create_file(&file_data("clip.mp4")).await.unwrap();
+        assert_eq!(file.application_id, application_id);
+        assert_eq!(client.read_file(file.id).await.unwrap().name, "clip.mp4");
+
+        client.update_cloud_status(file.id, &FileCloudStatus::Uploaded).await.unwrap();
+        let file = client.read_file(file.id).await.unwrap();
+        assert_eq!(file.cloud_status, FileCloudStatus::Uploaded);
+
+        let stream = client
+            .create_stream(&StreamData {
+                name: Some("Entrance".to_owned()),
+                source: StreamSource::UriStream,
+                stream_type: StreamType::Rtsp,
+                gateway_id: None,
+                uri: "rtsp://192.168.0.42/stream".parse().unwrap(),
+                status: None,
+                camera_id: None,
+                deployment_id: None,
+                node: None,
+                configuration: None,
+                snapshot_file_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(stream.status, StreamStatus::Unknown);
+
+        client.delete_file(file.id).await.unwrap();
+        let err = client.read_file(file.id).await.unwrap_err();
+        assert!(matches!(err, Error::Api(ApiError::ResourceNotFound(_), _)));
+        assert_eq!(server.records(Resource::Streams).len(), 1);
+        assert!(server.records(Resource::Files).is_empty());
+    }
+
+    #[tokio::test]
+    async fn streams_deployment_updates() {
+        let server = MockServer::start().await.unwrap();
+        let application_id = Uuid::new_v4();
+        let client = server.client_builder().application_id(application_id).build().unwrap();
+        let since: DateTime<Utc> = "2022-06-01T10:00:00Z".parse().unwrap();
+        let updated_ids: Vec<_> =
+            ["2022-05-31T10:00:00Z", "2022-06-01T10:00:00Z", "2022-06-02T10:00:00Z"]
+                .iter()
+                .map(|updated_at| {
+                    server.insert(
+                        Resource::Deployments,
+                        json!({
+                            "application_id": application_id,
+                            "updated_at": updated_at,
+                            "name": "Deployment",
+                            "pipeline_id": Uuid::nil(),
+                            "gateway_id": Uuid::nil(),
+                            "state": "running",
+                            "definition": [],
+                        }),
+                    )
+                })
+                .collect();
+
+        let deployments: Vec<_> = client
+            .get_deployment_updates_stream(since, Default::default())
+            .try_collect()
+            .await
+            .unwrap();
+
+        let mut ids: Vec<_> = deployments.iter().map(|deployment| deployment.id).collect();
+        ids.sort();
+        let mut expected = updated_ids[1..].to_vec();
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_resources() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client_builder().application_id(Uuid::new_v4()).build().unwrap();
+
+        let err = client.get_deployment(Uuid::new_v4()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Api(ApiError::ResourceNotFound(ResourceNotFound::DeploymentNotFound), _)
+        ));
+    }
+
+    #[tokio::test]
+    async fn injected_errors_are_retried() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client_builder().application_id(Uuid::new_v4()).build().unwrap();
+
+        server.inject_error(
+            ErrorInjection::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+                .method(Method::GET)
+                .times(2),
+        );
+
+        assert!(client.list_cameras(None).await.unwrap().is_empty());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_wait_for_retry_after() {
+        let server = MockServer::start().await.unwrap();
+        let client = server
+            .client_builder()
+            .application_id(Uuid::new_v4())
+            .retry_policy(RetryPolicy {
+                max_backoff: Duration::from_millis(1),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        server.inject_error(
+            ErrorInjection::new(StatusCode::TOO_MANY_REQUESTS, "rate-limited")
+                .context(json!({ "retry_after": 1 }))
+                .times(1),
+        );
+
+        let start = Instant::now();
+        client.list_cameras(None).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn injected_errors_surface_without_retries() {
+        let server = MockServer::start().await.unwrap();
+        let client = server
+            .client_builder()
+            .application_id(Uuid::new_v4())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+
+        server.inject_error(
+            ErrorInjection::new(StatusCode::BAD_REQUEST, "bad-request").path("/v1/apps"),
+        );
+
+        let err = client.list_cameras(None).await.unwrap_err();
+        match err {
+            Error::Api(ApiError::Other { code, .. }, details) => {
+                assert_eq!(code, "bad-request");
+                assert_eq!(details.status, Some(StatusCode::BAD_REQUEST));
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_refreshed() {
+        let server = MockServer::start().await.unwrap();
+        let credentials = LoginCredentials::new(
+            server.url(),
+            LoginParams { email: "user@example.com".to_owned(), password: "secret".to_owned() },
+        );
+        let client = ClientBuilder::with_credentials(server.url(), credentials)
+            .application_id(Uuid::new_v4())
+            .build()
+            .unwrap();
+
+        client.list_cameras(None).await.unwrap();
+
+        server.set_token(Some("rotated"));
+        client.list_cameras(None).await.unwrap();
+
+        let authorizations: Vec<_> = server
+            .requests()
+            .iter()
+            .filter_map(|request| {
+                request.headers.get("authorization")?.to_str().ok().map(ToOwned::to_owned)
+            })
+            .collect();
+        assert_eq!(authorizations, ["Bearer mock-token", "Bearer mock-token", "Bearer rotated"]);
+    }
+}
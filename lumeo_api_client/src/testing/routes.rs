@@ -0,0 +1,706 @@
+//! Request handling of the mock server, emulating the API server's routes on JSON records.
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use hyper::{
+    body::{self, Bytes},
+    header, Body, Method, Request, Response, StatusCode,
+};
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::{RecordedRequest, Resource, State, DEFAULT_TOKEN};
+use crate::error::ApiServerResponse;
+
+type Query = BTreeMap<String, Vec<String>>;
+
+pub(super) async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, request_body) = request.into_parts();
+    let request_body = body::to_bytes(request_body).await.unwrap_or_default();
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.requests.push(RecordedRequest {
+        method: parts.method.clone(),
+        path: parts.uri.path().to_owned(),
+        query: parts.uri.query().map(ToOwned::to_owned),
+        headers: parts.headers.clone(),
+        body: request_body.clone(),
+    });
+
+    if let Some(response) = injected_error(&mut state, &parts.method, parts.uri.path()) {
+        return Ok(response);
+    }
+
+    let segments: Vec<&str> = parts.uri.path().trim_matches('/').split('/').collect();
+    if let (&Method::POST, ["v1", "internal", "auth", "login"]) = (&parts.method, &*segments) {
+        let token = state.token.clone().unwrap_or_else(|| DEFAULT_TOKEN.to_owned());
+        return Ok(json_response(StatusCode::OK, &json!({ "token": token })));
+    }
+
+    // Signed storage URLs of file content, see `Client::download`.
+    if let (&Method::GET, ["storage", id, kind]) = (&parts.method, &*segments) {
+        let response = match state.contents.get(&format!("{id}/{kind}")) {
+            Some(content) => Response::new(Body::from(content.clone())),
+            None => json_response(StatusCode::NOT_FOUND, &not_found("file").1),
+        };
+        return Ok(response);
+    }
+
+    if let Some(token) = &state.token {
+        let authorized = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map_or(false, |value| value == token);
+        if !authorized {
+            let RouteError(status, error) = route_error(
+                StatusCode::UNAUTHORIZED,
+                "invalid-credentials",
+                "Invalid credentials",
+                None,
+            );
+            return Ok(json_response(status, &error));
+        }
+    }
+
+    let query = parse_query(parts.uri.query());
+    let response = route(&mut state, &parts.method, &segments, &query, &request_body)
+        .unwrap_or_else(|RouteError(status, error)| json_response(status, &error));
+    Ok(response)
+}
+
+fn injected_error(state: &mut State, method: &Method, path: &str) -> Option<Response<Body>> {
+    let injection = state.injections.iter_mut().find(|i| i.matches(method, path))?;
+    if let Some(remaining) = &mut injection.remaining {
+        *remaining -= 1;
+    }
+
+    let mut response = json_response(injection.status, &injection.response);
+    if let Some(retry_after) = injection.retry_after {
+        response.headers_mut().insert(header::RETRY_AFTER, retry_after.into());
+    }
+    Some(response)
+}
+
+/// Error answered by a route, kept small rather than a full [`Response`].
+struct RouteError(StatusCode, ApiServerResponse);
+
+type RouteResult<T = Response<Body>> = Result<T, RouteError>;
+
+/// Owner of the records a route acts on.
+#[derive(Clone, Copy)]
+enum Scope {
+    All,
+    App(Uuid),
+    Org(Uuid),
+}
+
+impl Scope {
+    fn key(self) -> Option<(&'static str, Uuid)> {
+        match self {
+            Scope::All => None,
+            Scope::App(id) => Some(("application_id", id)),
+            Scope::Org(id) => Some(("organization_id", id)),
+        }
+    }
+
+    fn contains(self, record: &JsonValue) -> bool {
+        self.key().map_or(true, |(key, id)| record[key] == json!(id))
+    }
+}
+
+fn route(
+    state: &mut State,
+    method: &Method,
+    segments: &[&str],
+    query: &Query,
+    body: &Bytes,
+) -> RouteResult {
+    match (method, segments) {
+        (&Method::GET, ["v1", "orgs"]) => {
+            Ok(ok(list(state, Resource::Organizations, Scope::All, query).into()))
+        }
+        (&Method::POST, ["v1", "orgs"]) => {
+            let mut org = parse_json(body)?;
+            org["account_id"] = json!(Uuid::new_v4());
+            Ok(ok(create(state, Resource::Organizations, Scope::All, org)?))
+        }
+        (_, ["v1", "orgs", org, rest @ ..]) => {
+            let org = parse_id(org, Resource::Organizations.name())?;
+            org_route(state, method, org, rest, query, body)
+        }
+        (_, ["v1", "apps", app]) => {
+            let app = parse_id(app, Resource::Applications.name())?;
+            item_route(state, method, Resource::Applications, Scope::All, app, body)
+        }
+        (&Method::GET, ["v1", "marketplace", "models", id]) => {
+            read(state, Resource::Models, Scope::All, id)
+        }
+        (&Method::POST, ["metrics", "v1", "gateways", _, "video_source_metrics"]) => {
+            create(state, Resource::VideoSourceMetrics, Scope::All, parse_json(body)?)?;
+            Ok(empty())
+        }
+        (&Method::POST, ["v1", "internal", "apps", app, "events", "error_events"]) => {
+            let app = parse_id(app, "application")?;
+            let error = parse_json(body)?;
+            let event = json!({
+                "category": "deployment",
+                "event_type": "deployment.error",
+                "severity": "error",
+                "payload": error.to_string(),
+                "object": "deployment",
+                "object_id": error.get("deployment_id"),
+            });
+            Ok(ok(create(state, Resource::Events, Scope::App(app), event)?))
+        }
+        (_, ["v1", "apps", app, rest @ ..]) => {
+            let app = parse_id(app, "application")?;
+            app_route(state, method, app, rest, query, body)
+        }
+        _ => Err(not_found("route")),
+    }
+}
+
+/// Reads, updates or deletes a single record.
+fn item_route(
+    state: &mut State,
+    method: &Method,
+    resource: Resource,
+    scope: Scope,
+    id: Uuid,
+    body: &Bytes,
+) -> RouteResult {
+    match *method {
+        Method::GET => Ok(ok(find(state, resource, scope, id)?)),
+        Method::PUT => Ok(ok(update(state, resource, scope, id, parse_json(body)?)?)),
+        Method::DELETE => {
+            find(state, resource, scope, id)?;
+            state.records.entry(resource).or_default().remove(&id);
+            Ok(empty())
+        }
+        _ => Err(not_found("route")),
+    }
+}
+
+fn org_route(
+    state: &mut State,
+    method: &Method,
+    org: Uuid,
+    segments: &[&str],
+    query: &Query,
+    body: &Bytes,
+) -> RouteResult {
+    let scope = Scope::Org(org);
+    let resource = match segments.first() {
+        None => return item_route(state, method, Resource::Organizations, Scope::All, org, body),
+        Some(&"apps") => Resource::Applications,
+        Some(&"invitations") => Resource::Invitations,
+        Some(&"members") => Resource::Members,
+        _ => return Err(not_found("route")),
+    };
+    find(state, Resource::Organizations, Scope::All, org)?;
+
+    match (method, &segments[1..]) {
+        (&Method::GET, []) => Ok(ok(list(state, resource, scope, query).into())),
+        (&Method::POST, []) if resource != Resource::Members => {
+            Ok(ok(create(state, resource, scope, parse_json(body)?)?))
+        }
+        (&Method::DELETE, [id]) if resource != Resource::Applications => {
+            let id = parse_id(id, resource.name())?;
+            item_route(state, method, resource, scope, id, body)
+        }
+        (&Method::PUT, [user_id, "role"]) if resource == Resource::Members => {
+            let user_id = parse_id(user_id, resource.name())?;
+            let role = String::from_utf8_lossy(body).into_owned();
+            update(state, resource, scope, user_id, json!({ "role": role }))?;
+            Ok(empty())
+        }
+        _ => Err(not_found("route")),
+    }
+}
+
+fn app_route(
+    state: &mut State,
+    method: &Method,
+    app: Uuid,
+    segments: &[&str],
+    query: &Query,
+    body: &Bytes,
+) -> RouteResult {
+    let scope = Scope::App(app);
+    let resource = match segments.first() {
+        Some(&"cameras") => Resource::Cameras,
+        Some(&"deployments") => Resource::Deployments,
+        Some(&"events") => Resource::Events,
+        Some(&"files") => Resource::Files,
+        Some(&"gateways") => Resource::Gateways,
+        Some(&"models") => Resource::Models,
+        Some(&"streams") => Resource::Streams,
+        _ => return Err(not_found("application")),
+    };
+
+    match (method, &segments[1..]) {
+        (&Method::GET, []) => Ok(ok(list(state, resource, scope, query).into())),
+        (&Method::POST, []) => {
+            let mut record = create(state, resource, scope, parse_json(body)?)?;
+            if resource == Resource::Gateways {
+                // Only returned on creation, like the API server does.
+                record["access_token"] = json!(Uuid::new_v4().simple().to_string());
+            }
+            Ok(ok(record))
+        }
+        (&Method::DELETE, []) => {
+            let ids: Vec<Uuid> = list(state, resource, scope, query)
+                .iter()
+                .filter_map(|record| record["id"].as_str()?.parse().ok())
+                .collect();
+            let records = state.records.entry(resource).or_default();
+            for id in ids {
+                records.remove(&id);
+            }
+            Ok(empty())
+        }
+        (_, [id]) => {
+            let id = parse_id(id, resource.name())?;
+            item_route(state, method, resource, scope, id, body)
+        }
+        (&Method::GET, [id, "definition"]) if resource == Resource::Deployments => {
+            let deployment = find(state, resource, scope, parse_id(id, resource.name())?)?;
+            let definition = match &deployment["definition"] {
+                JsonValue::String(definition) => serde_json::from_str(definition)
+                    .map_err(|_| bad_request("Invalid pipeline definition"))?,
+                definition => definition.clone(),
+            };
+            Ok(ok(definition))
+        }
+        (&Method::GET, [id, "logs"]) if resource == Resource::Deployments => {
+            let deployment = find(state, resource, scope, parse_id(id, resource.name())?)?;
+            let logs = deployment.get("logs").and_then(JsonValue::as_str).unwrap_or_default();
+            Ok(ok(json!({ "logs": logs })))
+        }
+        (&Method::POST, [id, action @ ("start" | "stop")]) if resource == Resource::Deployments => {
+            let id = parse_id(id, resource.name())?;
+            let state_name = if *action == "start" { "running" } else { "stopped" };
+            update(state, resource, scope, id, json!({ "state": state_name }))?;
+            Ok(empty())
+        }
+        (&Method::POST, [id, "snapshot"])
+            if matches!(resource, Resource::Cameras | Resource::Streams) =>
+        {
+            let id = parse_id(id, resource.name())?;
+            let source = find(state, resource, scope, id)?;
+            let source_key = if resource == Resource::Cameras { "camera_id" } else { "stream_id" };
+            let file = json!({
+                "name": "snapshot.jpg",
+                "size": 0,
+                "cloud_status": "uploaded",
+                "gateway_id": source.get("gateway_id"),
+                source_key: id,
+            });
+            let file = create(state, Resource::Files, scope, file)?;
+            Ok(ok(json!({ "file_id": file["id"] })))
+        }
+        (&Method::PUT, [id, "data", "chunks"]) if resource == Resource::Files => {
+            let id = parse_id(id, resource.name())?;
+            find(state, resource, scope, id)?;
+            let offset = query.get("offset").and_then(|o| o.first()?.parse::<usize>().ok());
+            let offset = offset.ok_or_else(|| bad_request("Missing offset"))?;
+            let content = state.contents.entry(format!("{id}/data")).or_default();
+            if offset > content.len() {
+                return Err(route_error(StatusCode::CONFLICT, "conflict", "Gap in upload", None));
+            }
+            let mut uploaded = content[..offset].to_vec();
+            uploaded.extend_from_slice(body);
+            *content = uploaded.into();
+            Ok(ok(json!({ "offset": content.len() })))
+        }
+        (&Method::POST, [id, "data", "complete"]) if resource == Resource::Files => {
+            let id = parse_id(id, resource.name())?;
+            find(state, resource, scope, id)?;
+            let completion = parse_json(body)?;
+            let content = state.contents.get(&format!("{id}/data")).cloned().unwrap_or_default();
+            let sha256: String =
+                Sha256::digest(&content).iter().map(|byte| format!("{byte:02x}")).collect();
+            if completion["size"] != json!(content.len()) || completion["sha256"] != json!(sha256) {
+                let message = "Uploaded content doesn't match";
+                return Err(route_error(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "validation-failed",
+                    message,
+                    None,
+                ));
+            }
+            let url = format!("{}/storage/{id}/data", state.url);
+            update(state, resource, scope, id, json!({ "data_url": url }))?;
+            Ok(empty())
+        }
+        (&Method::PUT, [id, kind @ ("data" | "metadata")]) if resource == Resource::Files => {
+            let id = parse_id(id, resource.name())?;
+            let url = format!("{}/storage/{id}/{kind}", state.url);
+            update(state, resource, scope, id, json!({ format!("{kind}_url"): url }))?;
+            state.contents.insert(format!("{id}/{kind}"), body.clone());
+            Ok(empty())
+        }
+        (&Method::PUT, [id, field]) if is_text_field(resource, field) => {
+            let id = parse_id(id, resource.name())?;
+            let value = String::from_utf8_lossy(body).into_owned();
+            update(state, resource, scope, id, json!({ *field: value }))?;
+            Ok(empty())
+        }
+        (&Method::GET, [id, "streams"]) if resource == Resource::Cameras => {
+            let id = parse_id(id, resource.name())?;
+            find(state, resource, scope, id)?;
+            let filter = [("camera_id".to_owned(), vec![id.to_string()])].into();
+            Ok(ok(list(state, Resource::Streams, scope, &filter).into()))
+        }
+        (&Method::GET, [id, "linked_cameras"]) if resource == Resource::Gateways => {
+            let id = parse_id(id, resource.name())?;
+            find(state, resource, scope, id)?;
+            let filter = [("gateway_id".to_owned(), vec![id.to_string()])].into();
+            Ok(ok(list(state, Resource::Cameras, scope, &filter).into()))
+        }
+        (&Method::POST, [id, "linked_cameras"]) if resource == Resource::Gateways => {
+            let gateway_id = parse_id(id, resource.name())?;
+            find(state, resource, scope, gateway_id)?;
+            let camera_id = parse_json(body)?["camera_id"]
+                .as_str()
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| bad_request("Expected a camera ID"))?;
+            update(
+                state,
+                Resource::Cameras,
+                scope,
+                camera_id,
+                json!({ "gateway_id": gateway_id }),
+            )?;
+            Ok(empty())
+        }
+        (&Method::DELETE, [id, "linked_cameras", camera_id]) if resource == Resource::Gateways => {
+            let gateway_id = parse_id(id, resource.name())?;
+            find(state, resource, scope, gateway_id)?;
+            let camera_id = parse_id(camera_id, Resource::Cameras.name())?;
+            let mut camera = update(state, Resource::Cameras, scope, camera_id, json!({}))?;
+            if camera["gateway_id"] != json!(gateway_id) {
+                return Err(not_found(Resource::Cameras.name()));
+            }
+            camera["gateway_id"] = JsonValue::Null;
+            state.records.entry(Resource::Cameras).or_default().insert(camera_id, camera);
+            Ok(empty())
+        }
+        (&Method::PUT, [id, "cameras_statuses"]) if resource == Resource::Gateways => {
+            let gateway_id = parse_id(id, resource.name())?;
+            let cameras: Vec<JsonValue> = serde_json::from_slice(body)
+                .map_err(|_| bad_request("Expected a list of cameras"))?;
+            for camera in cameras {
+                set_camera_status(state, app, gateway_id, camera);
+            }
+            Ok(empty())
+        }
+        (&Method::PUT, [gateway_id, "discovery_request", request_id])
+            if resource == Resource::Gateways =>
+        {
+            let gateway_id = parse_id(gateway_id, resource.name())?;
+            let request_id = parse_id(request_id, "discovery_request")?;
+            let mut request = parse_json(body)?;
+            request["id"] = json!(request_id);
+            request["gateway_id"] = json!(gateway_id);
+            request["expires_at"] = json!(Utc::now());
+            create(state, Resource::DiscoveryRequests, scope, request)?;
+            Ok(empty())
+        }
+        _ => Err(not_found("route")),
+    }
+}
+
+fn is_text_field(resource: Resource, field: &str) -> bool {
+    match resource {
+        Resource::Cameras => matches!(field, "status" | "snapshot_file_id"),
+        Resource::Files => field == "cloud_status",
+        Resource::Gateways => matches!(field, "ip_local" | "ip_ext"),
+        Resource::Streams => field == "snapshot_file_id",
+        _ => false,
+    }
+}
+
+/// Updates the status of the camera with the same MAC address or URI, or adds it.
+fn set_camera_status(state: &mut State, app: Uuid, gateway_id: Uuid, mut camera: JsonValue) {
+    camera["gateway_id"] = json!(gateway_id);
+    let cameras = state.records.entry(Resource::Cameras).or_default();
+    let existing = cameras.values_mut().find(|existing| {
+        existing["application_id"] == json!(app)
+            && existing["gateway_id"] == camera["gateway_id"]
+            && ["mac_address", "uri"]
+                .iter()
+                .any(|key| !camera[key].is_null() && existing[key] == camera[key])
+    });
+
+    match existing {
+        Some(existing) => merge(existing, camera),
+        None => {
+            let _ = create(state, Resource::Cameras, Scope::App(app), camera);
+        }
+    }
+}
+
+fn list(state: &State, resource: Resource, scope: Scope, query: &Query) -> Vec<JsonValue> {
+    let mut records: Vec<JsonValue> = state
+        .records
+        .get(&resource)
+        .into_iter()
+        .flat_map(BTreeMap::values)
+        .filter(|record| scope.contains(record))
+        .filter(|record| query.iter().all(|(key, values)| matches_filter(record, key, values)))
+        .cloned()
+        .collect();
+
+    records.sort_by_key(|record| std::cmp::Reverse(timestamp(record, "created_at")));
+    if let Some(key) = query.get("sort_by").and_then(|keys| keys.first()) {
+        records.sort_by(|a, b| compare(&a[key], &b[key]));
+        if query.get("sort_order").and_then(|orders| orders.first()).map_or(false, |o| o == "desc")
+        {
+            records.reverse();
+        }
+    }
+    let limit = query.get("limit").and_then(|l| l.first()?.parse::<usize>().ok());
+    if let Some(limit) = limit.filter(|&limit| limit > 0) {
+        records.truncate(limit);
+    }
+    records
+}
+
+fn matches_filter(record: &JsonValue, key: &str, values: &[String]) -> bool {
+    let bound = |suffix: &str| {
+        let field = key.strip_suffix(suffix)?;
+        let bound = DateTime::parse_from_rfc3339(values.first()?).ok()?;
+        Some((timestamp(record, &format!("{field}_at")), bound))
+    };
+
+    let bound_value = |prefix: &str| {
+        let field = key.strip_prefix(prefix)?;
+        Some((record[field].as_f64(), values.first()?.parse::<f64>().ok()?))
+    };
+
+    if matches!(key, "limit" | "sort_by" | "sort_order") {
+        true
+    } else if let Some((value, min)) = bound_value("min_") {
+        value.map_or(false, |value| value >= min)
+    } else if let Some((value, max)) = bound_value("max_") {
+        value.map_or(false, |value| value <= max)
+    } else if key == "name_pattern" {
+        let name = record["name"].as_str().unwrap_or_default();
+        values.iter().any(|pattern| matches_pattern(name, pattern))
+    } else if key == "has_local_path" {
+        values.contains(&(!record["local_path"].is_null()).to_string())
+    } else if let Some((time, since)) = bound("_ts_since") {
+        time.map_or(false, |time| time >= since)
+    } else if let Some((time, until)) = bound("_ts_until") {
+        time.map_or(false, |time| time < until)
+    } else {
+        let field = match key {
+            "states" => "state".to_owned(),
+            "statuses" => "status".to_owned(),
+            "severities" => "severity".to_owned(),
+            "event_types" => "event_type".to_owned(),
+            "cloud_statuses" => "cloud_status".to_owned(),
+            "conn_types" => "conn_type".to_owned(),
+            _ => key.strip_suffix('s').filter(|_| key.ends_with("_ids")).unwrap_or(key).to_owned(),
+        };
+        let value = match &record[&field] {
+            JsonValue::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        values.contains(&value)
+    }
+}
+
+/// Matches `name` against a pattern where `*` matches any characters.
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn compare(a: &JsonValue, b: &JsonValue) -> Ordering {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => {
+            a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal)
+        }
+        (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+        (a, b) => a.is_null().cmp(&b.is_null()).reverse(),
+    }
+}
+
+fn timestamp(record: &JsonValue, key: &str) -> Option<DateTime<Utc>> {
+    record[key].as_str()?.parse().ok()
+}
+
+fn find(state: &State, resource: Resource, scope: Scope, id: Uuid) -> RouteResult<JsonValue> {
+    state
+        .records
+        .get(&resource)
+        .and_then(|records| records.get(&id))
+        .filter(|record| scope.contains(record))
+        .cloned()
+        .ok_or_else(|| not_found(resource.name()))
+}
+
+fn read(state: &State, resource: Resource, scope: Scope, id: &str) -> RouteResult {
+    let id = parse_id(id, resource.name())?;
+    Ok(ok(find(state, resource, scope, id)?))
+}
+
+fn create(
+    state: &mut State,
+    resource: Resource,
+    scope: Scope,
+    data: JsonValue,
+) -> RouteResult<JsonValue> {
+    let mut record = json!({});
+    merge(&mut record, data);
+
+    let now = json!(Utc::now());
+    let id = record.get("id").and_then(|id| id.as_str()?.parse().ok()).unwrap_or_else(Uuid::new_v4);
+    record["id"] = json!(id);
+    if let Some((key, owner)) = scope.key() {
+        record[key] = json!(owner);
+    }
+    record["created_at"] = now.clone();
+    record["updated_at"] = now.clone();
+
+    let defaults = match resource {
+        Resource::Cameras | Resource::Streams => json!({ "name": "", "status": "unknown" }),
+        Resource::Deployments => json!({ "name": "", "state": "stopped", "definition": "[]" }),
+        Resource::Events => json!({ "event_ts": now }),
+        // Members are stored under their user id.
+        Resource::Members => json!({ "user_id": id }),
+        _ => json!({}),
+    };
+    for (key, value) in defaults.as_object().into_iter().flatten() {
+        if record[key].is_null() {
+            record[key] = value.clone();
+        }
+    }
+
+    state.records.entry(resource).or_default().insert(id, record.clone());
+    Ok(record)
+}
+
+fn update(
+    state: &mut State,
+    resource: Resource,
+    scope: Scope,
+    id: Uuid,
+    data: JsonValue,
+) -> RouteResult<JsonValue> {
+    find(state, resource, scope, id)?;
+    let record = state
+        .records
+        .get_mut(&resource)
+        .and_then(|records| records.get_mut(&id))
+        .ok_or_else(|| not_found(resource.name()))?;
+
+    merge(record, data);
+    record["updated_at"] = json!(Utc::now());
+    Ok(record.clone())
+}
+
+/// Copies the non-null fields of `data` into `record`, flattening the nested `data` object of
+/// `New*` bodies.
+fn merge(record: &mut JsonValue, data: JsonValue) {
+    let mut data = match data {
+        JsonValue::Object(data) => data,
+        _ => return,
+    };
+    if let Some(JsonValue::Object(nested)) = data.remove("data") {
+        data.extend(nested);
+    }
+
+    if let JsonValue::Object(record) = record {
+        record.extend(data.into_iter().filter(|(_, value)| !value.is_null()));
+    }
+}
+
+fn parse_query(query: Option<&str>) -> Query {
+    let mut parsed = Query::new();
+    for (key, value) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        parsed.entry(key.into_owned()).or_default().push(value.into_owned());
+    }
+    parsed
+}
+
+fn parse_id(id: &str, resource: &str) -> RouteResult<Uuid> {
+    id.parse().map_err(|_| not_found(resource))
+}
+
+fn parse_json(body: &Bytes) -> RouteResult<JsonValue> {
+    serde_json::from_slice(body).map_err(|_| bad_request("Invalid JSON body"))
+}
+
+fn ok(value: JsonValue) -> Response<Body> {
+    json_response(StatusCode::OK, &value)
+}
+
+fn empty() -> Response<Body> {
+    Response::new(Body::empty())
+}
+
+fn not_found(resource: &str) -> RouteError {
+    route_error(
+        StatusCode::NOT_FOUND,
+        "resource-not-found",
+        "Resource not found",
+        Some(json!({ "resource": resource })),
+    )
+}
+
+fn bad_request(message: &str) -> RouteError {
+    route_error(StatusCode::BAD_REQUEST, "bad-request", message, None)
+}
+
+fn route_error(
+    status: StatusCode,
+    code: &str,
+    message: &str,
+    context: Option<JsonValue>,
+) -> RouteError {
+    RouteError(
+        status,
+        ApiServerResponse { code: code.to_owned(), message: message.to_owned(), context },
+    )
+}
+
+fn json_response<T: serde::Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+    response
+}
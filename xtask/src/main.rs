@@ -4,7 +4,7 @@ use std::{collections::BTreeMap, fs, process};
 
 use anyhow::Context;
 use clap::Parser;
-use lumeo_api_client::pipeline::Pipeline;
+use lumeo_api_client::pipeline::{Configuration, Pipeline};
 use serde_json::Value as JsonValue;
 
 #[derive(Parser)]
@@ -33,54 +33,43 @@ fn main() {
     };
 
     if let Err(e) = res {
-        eprintln!("{e}");
+        eprintln!("{e:#}");
         process::exit(1);
     }
 }
 
-type Configuration = BTreeMap<String, serde_json::Map<String, JsonValue>>;
-
 fn check_pipeline(pipeline_file: &str, configuration_file: Option<&str>) -> anyhow::Result<()> {
-    let mut pipeline_json = fs::read_to_string(pipeline_file)?;
+    let pipeline_json = fs::read_to_string(pipeline_file)?;
+    let pipeline = parse_pipeline(&pipeline_json)?;
 
     if let Some(file) = configuration_file {
-        let configuration_json = fs::read_to_string(file)?;
-        let configuration = match serde_json::from_str::<Configuration>(&configuration_json) {
-            Ok(config) => config,
-            Err(e) => {
-                eprint!("Invalid pipeline configuration: ");
-                return Err(e.into());
-            }
-        };
-
-        update_pipeline_def(&mut pipeline_json, &configuration).context("configuring pipeline")?;
+        let configuration = read_configuration(file)?;
+        configure(&pipeline, configuration).context("configuring pipeline")?;
     }
 
-    match serde_json::from_str::<Pipeline>(&pipeline_json) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            eprint!("Invalid pipeline definition: ");
-            Err(e.into())
-        }
-    }
+    Ok(())
 }
 
 fn configure_pipeline(pipeline_file: &str, configuration_file: &str) -> Result<(), anyhow::Error> {
-    let mut pipeline_json = fs::read_to_string(pipeline_file)?;
-    let configuration_json = fs::read_to_string(configuration_file)?;
+    let pipeline_json = fs::read_to_string(pipeline_file)?;
+    let pipeline = parse_pipeline(&pipeline_json)?;
+    let configuration = read_configuration(configuration_file)?;
+
+    let pipeline = configure(&pipeline, configuration)?;
+
+    println!("{}", serde_json::to_string(&pipeline)?);
+    Ok(())
+}
 
-    let configuration = match serde_json::from_str::<Configuration>(&configuration_json) {
-        Ok(config) => config,
+fn read_configuration(configuration_file: &str) -> anyhow::Result<Configuration> {
+    let configuration_json = fs::read_to_string(configuration_file)?;
+    match serde_json::from_str::<Configuration>(&configuration_json) {
+        Ok(config) => Ok(config),
         Err(e) => {
             eprint!("Invalid pipeline configuration: ");
-            return Err(e.into());
+            Err(e.into())
         }
-    };
-
-    update_pipeline_def(&mut pipeline_json, &configuration)?;
-
-    println!("{pipeline_json}");
-    Ok(())
+    }
 }
 
 const DYNAMIC_NODES: &[&str] = &[
@@ -100,10 +89,8 @@ const DYNAMIC_NODES: &[&str] = &[
     "webhook_local",
 ];
 
-fn update_pipeline_def(
-    pipeline_json: &mut String,
-    configuration: &BTreeMap<String, serde_json::Map<String, JsonValue>>,
-) -> anyhow::Result<()> {
+/// Parses a pipeline definition without its dynamic nodes.
+fn parse_pipeline(pipeline_json: &str) -> anyhow::Result<Pipeline> {
     let mut pipeline_nodes: Vec<JsonValue> = serde_json::from_str(pipeline_json)?;
     pipeline_nodes.retain(|node| {
         let props_obj = node["properties"].as_object().expect("properties is not an object");
@@ -111,17 +98,19 @@ fn update_pipeline_def(
         !DYNAMIC_NODES.contains(&node_type)
     });
 
-    for node in &mut pipeline_nodes {
-        let node_id = node["id"].as_str().context("id is not a string")?.to_owned();
-        let props_obj =
-            node["properties"].as_object_mut().context("properties is not an object")?;
-
-        if let Some(config_props) = configuration.get(&node_id) {
-            props_obj.extend(config_props.clone());
+    match serde_json::from_value::<Pipeline>(JsonValue::Array(pipeline_nodes)) {
+        Ok(pipeline) => Ok(pipeline),
+        Err(e) => {
+            eprint!("Invalid pipeline definition: ");
+            Err(e.into())
         }
     }
+}
 
-    *pipeline_json = serde_json::to_string(&pipeline_nodes)?;
+/// Applies `configuration` to `pipeline`, ignoring the configuration of dynamic nodes.
+fn configure(pipeline: &Pipeline, configuration: Configuration) -> anyhow::Result<Pipeline> {
+    let mut nodes: BTreeMap<_, _> = configuration.into();
+    nodes.retain(|node_id, _| pipeline.node_by_id(node_id).is_some());
 
-    Ok(())
+    Ok(pipeline.configure(&nodes.into())?)
 }